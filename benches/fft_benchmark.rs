@@ -1,7 +1,7 @@
 use criterion::*;
 use nalgebra::{Complex, DVector};
 use rand::Rng;
-use siras::fft::{fft, ifft};
+use siras::fft::{fft, ifft, irfft, rfft, FftPlanner};
 
 fn fft_benchmark(c: &mut Criterion) {
     let n = 1024;
@@ -17,6 +17,56 @@ fn ifft_benchmark(c: &mut Criterion) {
     c.bench_function("ifft", |b| b.iter(|| ifft(black_box(&spectrum))));
 }
 
-criterion_group!(benches, fft_benchmark, ifft_benchmark);
+fn fft_any_length_benchmark(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+    // 1000 = 2^3 * 5^3 uses the mixed-radix path and 1009 is a prime handled by Bluestein
+    for n in [1000, 1009] {
+        let signal = DVector::<f64>::from_fn(n, |_, _| rng.gen());
+        c.bench_function(&format!("fft {}", n), |b| {
+            b.iter(|| fft(black_box(&signal)))
+        });
+    }
+}
+
+fn rfft_benchmark(c: &mut Criterion) {
+    let n = 1024;
+    let mut rng = rand::thread_rng();
+    let signal = DVector::<f64>::from_fn(n, |_, _| rng.gen());
+    c.bench_function("rfft", |b| b.iter(|| rfft(black_box(&signal))));
+
+    let spectrum = rfft(&signal);
+    c.bench_function("irfft", |b| b.iter(|| irfft(black_box(&spectrum), n)));
+}
+
+fn planned_fft_benchmark(c: &mut Criterion) {
+    let n = 1024;
+    let mut rng = rand::thread_rng();
+    let frame = DVector::<Complex<f64>>::from_fn(n, |_, _| Complex::new(rng.gen(), rng.gen()));
+    let mut buffer = frame.clone();
+    let mut planner = FftPlanner::new();
+
+    // Copying the frame in keeps the values bounded across iterations
+    c.bench_function("planned fft", |b| {
+        b.iter(|| {
+            buffer.copy_from(&frame);
+            planner.plan(n).forward(black_box(buffer.as_mut_slice()));
+        })
+    });
+    c.bench_function("planned ifft", |b| {
+        b.iter(|| {
+            buffer.copy_from(&frame);
+            planner.plan(n).inverse(black_box(buffer.as_mut_slice()));
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    fft_benchmark,
+    ifft_benchmark,
+    fft_any_length_benchmark,
+    rfft_benchmark,
+    planned_fft_benchmark
+);
 
 criterion_main!(benches);
@@ -0,0 +1,498 @@
+use nalgebra::{Complex, ComplexField, DVector, Scalar};
+
+use crate::fft::{fft_complex, ifft, irfft, rfft, FftPlan};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConvolveMode {
+    // Every point of overlap, n + m - 1 samples.
+    Full,
+    // The length of the first input, centered on the full output.
+    Same,
+    // Only the points where the inputs overlap completely, max(n, m) - min(n, m) + 1 samples.
+    Valid,
+}
+
+// Rough cost of one FFT butterfly relative to one multiply-add of the direct sum, used to pick
+// between the two.
+const FFT_COST: f64 = 4.0;
+
+pub fn convolve(a: &DVector<f64>, b: &DVector<f64>, mode: ConvolveMode) -> DVector<f64> {
+    if prefers_fft(a.len(), b.len()) {
+        fftconvolve(a, b, mode)
+    } else {
+        crop(direct_convolve(a, b), a.len(), b.len(), mode)
+    }
+}
+
+pub fn convolve_complex(
+    a: &DVector<Complex<f64>>,
+    b: &DVector<Complex<f64>>,
+    mode: ConvolveMode,
+) -> DVector<Complex<f64>> {
+    if prefers_fft(a.len(), b.len()) {
+        fftconvolve_complex(a, b, mode)
+    } else {
+        crop(direct_convolve(a, b), a.len(), b.len(), mode)
+    }
+}
+
+// Correlation as in numpy: c[k] = sum_n a[n + k] conj(b[n]), with the lags of the full output
+// running from -(m - 1) to n - 1.
+pub fn correlate(a: &DVector<f64>, b: &DVector<f64>, mode: ConvolveMode) -> DVector<f64> {
+    convolve(a, &reversed(b), mode)
+}
+
+pub fn correlate_complex(
+    a: &DVector<Complex<f64>>,
+    b: &DVector<Complex<f64>>,
+    mode: ConvolveMode,
+) -> DVector<Complex<f64>> {
+    convolve_complex(a, &reversed(b).map(|c| c.conj()), mode)
+}
+
+// Lag of each sample of correlate(a, b, mode) for inputs of lengths n and m. A positive lag
+// means that a is delayed relative to b.
+pub fn correlation_lags(n: usize, m: usize, mode: ConvolveMode) -> DVector<isize> {
+    if n == 0 || m == 0 {
+        return DVector::from_vec(Vec::new());
+    }
+    let full = DVector::from_fn(n + m - 1, |i, _| i as isize - (m as isize - 1));
+    crop(full, n, m, mode)
+}
+
+pub fn fftconvolve(a: &DVector<f64>, b: &DVector<f64>, mode: ConvolveMode) -> DVector<f64> {
+    if a.is_empty() || b.is_empty() {
+        return DVector::zeros(0);
+    }
+    let length = a.len() + b.len() - 1;
+    let fft_length = length.next_power_of_two();
+
+    let spectrum =
+        rfft(&zero_padded(a, fft_length)).component_mul(&rfft(&zero_padded(b, fft_length)));
+    let full = irfft(&spectrum, fft_length).rows(0, length).into_owned();
+
+    crop(full, a.len(), b.len(), mode)
+}
+
+pub fn fftconvolve_complex(
+    a: &DVector<Complex<f64>>,
+    b: &DVector<Complex<f64>>,
+    mode: ConvolveMode,
+) -> DVector<Complex<f64>> {
+    if a.is_empty() || b.is_empty() {
+        return DVector::zeros(0);
+    }
+    let length = a.len() + b.len() - 1;
+    let fft_length = length.next_power_of_two();
+
+    let spectrum = fft_complex(&zero_padded(a, fft_length))
+        .component_mul(&fft_complex(&zero_padded(b, fft_length)));
+    let full = ifft(&spectrum).rows(0, length).into_owned();
+
+    crop(full, a.len(), b.len(), mode)
+}
+
+// Streaming convolution with a fixed kernel. Each input block is convolved on its own and the
+// last m - 1 samples are carried over into the next block.
+#[derive(Clone, Debug)]
+pub struct OverlapAdd {
+    kernel: BlockKernel,
+    tail: DVector<f64>,
+}
+
+impl OverlapAdd {
+    pub fn new(kernel: &DVector<f64>, block_length: usize) -> Self {
+        let kernel = BlockKernel::new(kernel, block_length);
+        let tail = DVector::zeros(kernel.taps - 1);
+        Self { kernel, tail }
+    }
+
+    // Filters the next chunk of the stream and returns as many samples as it was given, as a
+    // causal FIR filter would.
+    pub fn process(&mut self, x: &DVector<f64>) -> DVector<f64> {
+        let overlap = self.tail.len();
+        let mut output = DVector::zeros(x.len());
+
+        for start in (0..x.len()).step_by(self.kernel.block_length) {
+            let length = self.kernel.block_length.min(x.len() - start);
+            let mut block = self.kernel.filter(x.rows(start, length).iter().copied());
+            for (b, t) in block.iter_mut().zip(self.tail.iter()) {
+                *b += t;
+            }
+            output
+                .rows_mut(start, length)
+                .copy_from_slice(&block[..length]);
+            self.tail.copy_from_slice(&block[length..length + overlap]);
+        }
+
+        output
+    }
+
+    // The remaining m - 1 samples of the full convolution. The state is cleared afterwards.
+    pub fn flush(&mut self) -> DVector<f64> {
+        let tail = self.tail.clone();
+        self.reset();
+        tail
+    }
+
+    pub fn reset(&mut self) {
+        self.tail.fill(0.0);
+    }
+}
+
+// Streaming convolution with a fixed kernel. Each block is transformed together with the last
+// m - 1 input samples, and the outputs corrupted by circular wrap-around are discarded.
+#[derive(Clone, Debug)]
+pub struct OverlapSave {
+    kernel: BlockKernel,
+    history: DVector<f64>,
+}
+
+impl OverlapSave {
+    pub fn new(kernel: &DVector<f64>, block_length: usize) -> Self {
+        let kernel = BlockKernel::new(kernel, block_length);
+        let history = DVector::zeros(kernel.taps - 1);
+        Self { kernel, history }
+    }
+
+    // Filters the next chunk of the stream and returns as many samples as it was given, as a
+    // causal FIR filter would.
+    pub fn process(&mut self, x: &DVector<f64>) -> DVector<f64> {
+        let overlap = self.history.len();
+        let mut output = DVector::zeros(x.len());
+
+        for start in (0..x.len()).step_by(self.kernel.block_length) {
+            let length = self.kernel.block_length.min(x.len() - start);
+            let input = self
+                .history
+                .iter()
+                .chain(x.rows(start, length).iter())
+                .copied()
+                .collect::<Vec<_>>();
+            let block = self.kernel.filter(input.iter().copied());
+            output
+                .rows_mut(start, length)
+                .copy_from_slice(&block[overlap..overlap + length]);
+            self.history
+                .copy_from_slice(&input[input.len() - overlap..]);
+        }
+
+        output
+    }
+
+    // The remaining m - 1 samples of the full convolution. The state is cleared afterwards.
+    pub fn flush(&mut self) -> DVector<f64> {
+        let tail = self.process(&DVector::zeros(self.history.len()));
+        self.reset();
+        tail
+    }
+
+    pub fn reset(&mut self) {
+        self.history.fill(0.0);
+    }
+}
+
+// Kernel spectrum and scratch space shared by the block convolutions. The transform is long
+// enough for one block plus the m - 1 samples of overlap without wrapping around.
+#[derive(Clone, Debug)]
+struct BlockKernel {
+    taps: usize,
+    block_length: usize,
+    spectrum: Vec<Complex<f64>>,
+    plan: FftPlan,
+    scratch: Vec<Complex<f64>>,
+}
+
+impl BlockKernel {
+    fn new(kernel: &DVector<f64>, block_length: usize) -> Self {
+        assert!(!kernel.is_empty(), "The kernel must not be empty.");
+        assert!(block_length > 0, "The block length must be positive.");
+
+        let taps = kernel.len();
+        let fft_length = (block_length + taps - 1).next_power_of_two();
+        let mut plan = FftPlan::new(fft_length);
+        let mut spectrum = vec![Complex::new(0.0, 0.0); fft_length];
+        for (s, k) in spectrum.iter_mut().zip(kernel.iter()) {
+            *s = Complex::new(*k, 0.0);
+        }
+        plan.forward(&mut spectrum);
+
+        Self {
+            taps,
+            block_length,
+            spectrum,
+            plan,
+            scratch: vec![Complex::new(0.0, 0.0); fft_length],
+        }
+    }
+
+    // Circular convolution of the zero-padded input with the kernel, over the whole transform
+    // length.
+    fn filter(&mut self, x: impl Iterator<Item = f64>) -> Vec<f64> {
+        self.scratch.fill(Complex::new(0.0, 0.0));
+        for (s, v) in self.scratch.iter_mut().zip(x) {
+            *s = Complex::new(v, 0.0);
+        }
+        self.plan.forward(&mut self.scratch);
+        for (s, k) in self.scratch.iter_mut().zip(self.spectrum.iter()) {
+            *s *= k;
+        }
+        self.plan.inverse(&mut self.scratch);
+
+        self.scratch.iter().map(|c| c.re).collect()
+    }
+}
+
+fn prefers_fft(n: usize, m: usize) -> bool {
+    if n == 0 || m == 0 {
+        return false;
+    }
+    let fft_length = (n + m - 1).next_power_of_two() as f64;
+    let fft_cost = 3.0 * FFT_COST * fft_length * fft_length.log2().max(1.0);
+    (n * m) as f64 > fft_cost
+}
+
+fn direct_convolve<T: ComplexField + Copy>(a: &DVector<T>, b: &DVector<T>) -> DVector<T> {
+    let n = a.len();
+    let m = b.len();
+    if n == 0 || m == 0 {
+        return DVector::zeros(0);
+    }
+
+    let mut result = DVector::zeros(n + m - 1);
+    for i in 0..n {
+        for j in 0..m {
+            result[i + j] += a[i] * b[j];
+        }
+    }
+
+    result
+}
+
+fn crop<T: Scalar>(full: DVector<T>, n: usize, m: usize, mode: ConvolveMode) -> DVector<T> {
+    if n == 0 || m == 0 {
+        return DVector::from_vec(Vec::new());
+    }
+    let (start, length) = match mode {
+        ConvolveMode::Full => return full,
+        ConvolveMode::Same => ((m - 1) / 2, n),
+        ConvolveMode::Valid => (n.min(m) - 1, n.max(m) - n.min(m) + 1),
+    };
+
+    full.rows(start, length).into_owned()
+}
+
+fn reversed<T: ComplexField + Copy>(x: &DVector<T>) -> DVector<T> {
+    DVector::from_iterator(x.len(), x.iter().rev().copied())
+}
+
+fn zero_padded<T: ComplexField + Copy>(x: &DVector<T>, n: usize) -> DVector<T> {
+    DVector::from_fn(n, |i, _| if i < x.len() { x[i] } else { T::zero() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::dvector;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use rstest::rstest;
+
+    fn random_signal(n: usize, seed: u64) -> DVector<f64> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        DVector::from_fn(n, |_, _| rng.gen_range(-1.0..1.0))
+    }
+
+    #[rstest]
+    #[case(ConvolveMode::Full, dvector![0.0, 1.0, 2.5, 4.0, 1.5])]
+    #[case(ConvolveMode::Same, dvector![1.0, 2.5, 4.0])]
+    #[case(ConvolveMode::Valid, dvector![2.5])]
+    fn test_convolve_modes(#[case] mode: ConvolveMode, #[case] expected: DVector<f64>) {
+        let a = dvector![1.0, 2.0, 3.0];
+        let b = dvector![0.0, 1.0, 0.5];
+        assert_relative_eq!(convolve(&a, &b, mode), expected);
+        assert_relative_eq!(fftconvolve(&a, &b, mode), expected, epsilon = 1e-12);
+    }
+
+    #[rstest]
+    #[case(ConvolveMode::Full, dvector![0.5, 2.0, 3.5, 3.0, 0.0])]
+    #[case(ConvolveMode::Same, dvector![2.0, 3.5, 3.0])]
+    #[case(ConvolveMode::Valid, dvector![3.5])]
+    fn test_correlate_modes(#[case] mode: ConvolveMode, #[case] expected: DVector<f64>) {
+        let a = dvector![1.0, 2.0, 3.0];
+        let b = dvector![0.0, 1.0, 0.5];
+        assert_relative_eq!(correlate(&a, &b, mode), expected);
+    }
+
+    #[rstest]
+    #[case(3, 3, ConvolveMode::Full, vec![-2, -1, 0, 1, 2])]
+    #[case(3, 3, ConvolveMode::Same, vec![-1, 0, 1])]
+    #[case(5, 2, ConvolveMode::Valid, vec![0, 1, 2, 3])]
+    #[case(2, 5, ConvolveMode::Valid, vec![-3, -2, -1, 0])]
+    #[case(4, 3, ConvolveMode::Same, vec![-1, 0, 1, 2])]
+    #[case(0, 0, ConvolveMode::Full, vec![])]
+    #[case(0, 3, ConvolveMode::Same, vec![])]
+    #[case(3, 0, ConvolveMode::Valid, vec![])]
+    fn test_correlation_lags(
+        #[case] n: usize,
+        #[case] m: usize,
+        #[case] mode: ConvolveMode,
+        #[case] expected: Vec<isize>,
+    ) {
+        assert_eq!(correlation_lags(n, m, mode), DVector::from_vec(expected));
+    }
+
+    #[test]
+    fn test_correlation_lags_locate_delay() {
+        let b = random_signal(200, 7);
+        let a = DVector::from_fn(250, |i, _| {
+            if (30..230).contains(&i) {
+                b[i - 30]
+            } else {
+                0.0
+            }
+        });
+
+        for mode in [ConvolveMode::Full, ConvolveMode::Same, ConvolveMode::Valid] {
+            let c = correlate(&a, &b, mode);
+            let peak = c.argmax().0;
+            assert_eq!(correlation_lags(a.len(), b.len(), mode)[peak], 30);
+        }
+    }
+
+    #[test]
+    fn test_valid_mode_with_longer_second_input() {
+        let a = dvector![1.0, 2.0];
+        let b = dvector![1.0, 0.0, -1.0, 2.0];
+        assert_relative_eq!(
+            convolve(&a, &b, ConvolveMode::Valid),
+            dvector![2.0, -1.0, 0.0]
+        );
+        assert_relative_eq!(convolve(&a, &b, ConvolveMode::Same), dvector![2.0, -1.0]);
+    }
+
+    #[test]
+    fn test_correlate_complex() {
+        let a = dvector![
+            Complex::new(1.0, 0.0),
+            Complex::new(2.0, 0.0),
+            Complex::new(3.0, 0.0),
+        ];
+        let b = dvector![
+            Complex::new(0.0, 0.0),
+            Complex::new(1.0, 0.0),
+            Complex::new(0.5, 0.0),
+        ];
+        let real = correlate_complex(&a, &b, ConvolveMode::Full).map(|e| e.re);
+        assert_relative_eq!(real, dvector![0.5, 2.0, 3.5, 3.0, 0.0]);
+
+        let a = dvector![
+            Complex::new(1.0, 1.0),
+            Complex::new(2.0, 0.0),
+            Complex::new(3.0, -1.0),
+        ];
+        let b = dvector![
+            Complex::new(0.0, 0.0),
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 0.5),
+        ];
+        let result = correlate_complex(&a, &b, ConvolveMode::Full);
+        assert_relative_eq!(result.map(|e| e.re), dvector![0.5, 1.0, 1.5, 3.0, 0.0]);
+        assert_relative_eq!(result.map(|e| e.im), dvector![-0.5, 0.0, -1.5, -1.0, 0.0]);
+
+        let a = dvector![
+            Complex::new(1.0, 0.0),
+            Complex::new(2.0, 0.0),
+            Complex::new(3.0, 0.0),
+        ];
+        let b = dvector![Complex::new(4.0, 0.0), Complex::new(5.0, 0.0)];
+        let result = correlate_complex(&a, &b, ConvolveMode::Full);
+        assert_relative_eq!(result.map(|e| e.re), dvector![5.0, 14.0, 23.0, 12.0]);
+        assert_relative_eq!(result.map(|e| e.im), dvector![0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[rstest]
+    #[case(ConvolveMode::Full)]
+    #[case(ConvolveMode::Same)]
+    #[case(ConvolveMode::Valid)]
+    fn test_fftconvolve_matches_direct(#[case] mode: ConvolveMode) {
+        let a = random_signal(1000, 1);
+        let b = random_signal(301, 2);
+        let expected = crop(direct_convolve(&a, &b), a.len(), b.len(), mode);
+        assert_relative_eq!(fftconvolve(&a, &b, mode), expected, epsilon = 1e-10);
+        assert_relative_eq!(convolve(&a, &b, mode), expected, epsilon = 1e-10);
+
+        let a_complex = a.zip_map(&random_signal(1000, 3), Complex::new);
+        let b_complex = b.zip_map(&random_signal(301, 4), Complex::new);
+        let expected = crop(
+            direct_convolve(&a_complex, &b_complex),
+            a.len(),
+            b.len(),
+            mode,
+        );
+        let result = fftconvolve_complex(&a_complex, &b_complex, mode);
+        assert_relative_eq!(
+            result.map(|c| c.re),
+            expected.map(|c| c.re),
+            epsilon = 1e-10
+        );
+        assert_relative_eq!(
+            result.map(|c| c.im),
+            expected.map(|c| c.im),
+            epsilon = 1e-10
+        );
+    }
+
+    #[test]
+    fn test_method_selection() {
+        assert!(!prefers_fft(100, 3));
+        assert!(!prefers_fft(0, 1000));
+        assert!(prefers_fft(10_000, 1000));
+    }
+
+    #[test]
+    fn test_empty_inputs() {
+        let empty = DVector::<f64>::zeros(0);
+        assert_eq!(
+            convolve(&empty, &dvector![1.0], ConvolveMode::Full).len(),
+            0
+        );
+        assert_eq!(
+            fftconvolve(&dvector![1.0], &empty, ConvolveMode::Same).len(),
+            0
+        );
+    }
+
+    #[rstest]
+    #[case(64)]
+    #[case(100)]
+    #[case(1)]
+    fn test_block_convolution(#[case] block_length: usize) {
+        let x = random_signal(1500, 5);
+        let kernel = random_signal(37, 6);
+        let expected = direct_convolve(&x, &kernel);
+
+        // Uneven chunks exercise the carried state across calls
+        let chunks = [0, 250, 251, 900, 1500];
+        let mut overlap_add = OverlapAdd::new(&kernel, block_length);
+        let mut overlap_save = OverlapSave::new(&kernel, block_length);
+        let mut add_output = Vec::new();
+        let mut save_output = Vec::new();
+        for pair in chunks.windows(2) {
+            let chunk = x.rows(pair[0], pair[1] - pair[0]).into_owned();
+            add_output.extend(overlap_add.process(&chunk).iter());
+            save_output.extend(overlap_save.process(&chunk).iter());
+        }
+        add_output.extend(overlap_add.flush().iter());
+        save_output.extend(overlap_save.flush().iter());
+
+        assert_relative_eq!(DVector::from_vec(add_output), expected, epsilon = 1e-10);
+        assert_relative_eq!(DVector::from_vec(save_output), expected, epsilon = 1e-10);
+
+        // Flushing clears the state for the next stream
+        let impulse = DVector::from_fn(37, |i, _| if i == 0 { 1.0 } else { 0.0 });
+        assert_relative_eq!(overlap_add.process(&impulse), kernel, epsilon = 1e-12);
+        assert_relative_eq!(overlap_save.process(&impulse), kernel, epsilon = 1e-12);
+    }
+}
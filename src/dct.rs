@@ -0,0 +1,390 @@
+use std::f64::consts::{PI, SQRT_2};
+
+use nalgebra::{Complex, DVector};
+
+use crate::fft::{fft_complex, ifft, FftNorm};
+
+// Type of a discrete cosine or sine transform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransformType {
+    I,
+    II,
+    III,
+    IV,
+}
+
+impl TransformType {
+    fn inverse(&self) -> Self {
+        match self {
+            TransformType::II => TransformType::III,
+            TransformType::III => TransformType::II,
+            _ => *self,
+        }
+    }
+}
+
+// The unnormalized transforms follow scipy.fft, e.g. DCT-II is y[k] = 2 sum x[n] cos(pi k (2n + 1) / 2N).
+pub fn dct(x: &DVector<f64>, transform_type: TransformType, norm: FftNorm) -> DVector<f64> {
+    let n = x.len();
+    if n == 0 {
+        return DVector::zeros(0);
+    }
+    match norm {
+        FftNorm::Backward => dct_unnormalized(x, transform_type),
+        FftNorm::Forward => dct_unnormalized(x, transform_type) / dct_scale(n, transform_type),
+        FftNorm::Ortho => {
+            let mut x = x.clone();
+            match transform_type {
+                TransformType::I => {
+                    x[0] *= SQRT_2;
+                    x[n - 1] *= SQRT_2;
+                }
+                TransformType::III => x[0] *= SQRT_2,
+                _ => {}
+            }
+            let mut y = dct_unnormalized(&x, transform_type);
+            match transform_type {
+                TransformType::I => {
+                    y[0] /= SQRT_2;
+                    y[n - 1] /= SQRT_2;
+                }
+                TransformType::II => y[0] /= SQRT_2,
+                _ => {}
+            }
+            y / dct_scale(n, transform_type).sqrt()
+        }
+    }
+}
+
+pub fn idct(x: &DVector<f64>, transform_type: TransformType, norm: FftNorm) -> DVector<f64> {
+    let n = x.len();
+    let inverse_type = transform_type.inverse();
+    match norm {
+        FftNorm::Backward => dct_unnormalized(x, inverse_type) / dct_scale(n, transform_type),
+        FftNorm::Forward => dct_unnormalized(x, inverse_type),
+        FftNorm::Ortho => dct(x, inverse_type, FftNorm::Ortho),
+    }
+}
+
+pub fn dst(x: &DVector<f64>, transform_type: TransformType, norm: FftNorm) -> DVector<f64> {
+    let n = x.len();
+    if n == 0 {
+        return DVector::zeros(0);
+    }
+    match norm {
+        FftNorm::Backward => dst_unnormalized(x, transform_type),
+        FftNorm::Forward => dst_unnormalized(x, transform_type) / dst_scale(n, transform_type),
+        FftNorm::Ortho => {
+            let mut x = x.clone();
+            if transform_type == TransformType::III {
+                x[n - 1] *= SQRT_2;
+            }
+            let mut y = dst_unnormalized(&x, transform_type);
+            if transform_type == TransformType::II {
+                y[n - 1] /= SQRT_2;
+            }
+            y / dst_scale(n, transform_type).sqrt()
+        }
+    }
+}
+
+pub fn idst(x: &DVector<f64>, transform_type: TransformType, norm: FftNorm) -> DVector<f64> {
+    let n = x.len();
+    let inverse_type = transform_type.inverse();
+    match norm {
+        FftNorm::Backward => dst_unnormalized(x, inverse_type) / dst_scale(n, transform_type),
+        FftNorm::Forward => dst_unnormalized(x, inverse_type),
+        FftNorm::Ortho => dst(x, inverse_type, FftNorm::Ortho),
+    }
+}
+
+// Applying a transform and then its inverse type multiplies by this factor.
+fn dct_scale(n: usize, transform_type: TransformType) -> f64 {
+    match transform_type {
+        TransformType::I => 2.0 * (n as f64 - 1.0),
+        _ => 2.0 * n as f64,
+    }
+}
+
+fn dst_scale(n: usize, transform_type: TransformType) -> f64 {
+    match transform_type {
+        TransformType::I => 2.0 * (n as f64 + 1.0),
+        _ => 2.0 * n as f64,
+    }
+}
+
+fn dct_unnormalized(x: &DVector<f64>, transform_type: TransformType) -> DVector<f64> {
+    let n = x.len();
+    if n == 0 {
+        return DVector::zeros(0);
+    }
+
+    match transform_type {
+        // Real part of the FFT of the even extension
+        TransformType::I => {
+            assert!(n >= 2, "The DCT-I needs at least 2 samples.");
+            let extended = DVector::from_fn(2 * (n - 1), |i, _| {
+                Complex::new(if i < n { x[i] } else { x[2 * (n - 1) - i] }, 0.0)
+            });
+            fft_complex(&extended).rows(0, n).map(|c| c.re)
+        }
+        // Even samples in order followed by odd samples in reverse (Makhoul)
+        TransformType::II => {
+            let v = DVector::from_fn(n, |i, _| {
+                let j = if 2 * i < n { 2 * i } else { 2 * (n - i) - 1 };
+                Complex::new(x[j], 0.0)
+            });
+            let v = fft_complex(&v);
+            DVector::from_fn(n, |k, _| {
+                2.0 * (v[k] * Complex::new(0.0, -PI * k as f64 / (2 * n) as f64).exp()).re
+            })
+        }
+        TransformType::III => {
+            let v = DVector::from_fn(n, |k, _| {
+                let reflected = if k == 0 { 0.0 } else { x[n - k] };
+                Complex::new(0.0, PI * k as f64 / (2 * n) as f64).exp()
+                    * Complex::new(x[k], -reflected)
+            });
+            let v = ifft(&v) * Complex::new(n as f64, 0.0);
+            DVector::from_fn(n, |i, _| {
+                if i % 2 == 0 {
+                    v[i / 2].re
+                } else {
+                    v[n - 1 - i / 2].re
+                }
+            })
+        }
+        // Zero-padded transform of length 2N with pre- and post-twiddles
+        TransformType::IV => {
+            let u = DVector::from_fn(2 * n, |i, _| {
+                if i < n {
+                    x[i] * Complex::new(0.0, -PI * i as f64 / (2 * n) as f64).exp()
+                } else {
+                    Complex::new(0.0, 0.0)
+                }
+            });
+            let u = fft_complex(&u);
+            DVector::from_fn(n, |k, _| {
+                let twiddle = Complex::new(0.0, -PI * (2 * k + 1) as f64 / (4 * n) as f64).exp();
+                2.0 * (twiddle * u[k]).re
+            })
+        }
+    }
+}
+
+fn dst_unnormalized(x: &DVector<f64>, transform_type: TransformType) -> DVector<f64> {
+    let n = x.len();
+    if n == 0 {
+        return DVector::zeros(0);
+    }
+    let alternate =
+        |v: DVector<f64>| v.map_with_location(|k, _, v| if k % 2 == 0 { v } else { -v });
+    let reversed = DVector::from_fn(n, |i, _| x[n - 1 - i]);
+
+    match transform_type {
+        // Imaginary part of the FFT of the odd extension
+        TransformType::I => {
+            let extended = DVector::from_fn(2 * (n + 1), |i, _| {
+                let value = if i == 0 || i == n + 1 {
+                    0.0
+                } else if i <= n {
+                    x[i - 1]
+                } else {
+                    -x[2 * n + 1 - i]
+                };
+                Complex::new(value, 0.0)
+            });
+            -fft_complex(&extended).rows(1, n).map(|c| c.im)
+        }
+        // The DST-II of x is the reversed DCT-II of x with alternating signs
+        TransformType::II => {
+            let y = dct_unnormalized(&alternate(x.clone()), TransformType::II);
+            DVector::from_fn(n, |k, _| y[n - 1 - k])
+        }
+        // The DST-III and DST-IV of x are the DCT-III and DCT-IV of x reversed, with
+        // alternating signs
+        TransformType::III => alternate(dct_unnormalized(&reversed, TransformType::III)),
+        TransformType::IV => alternate(dct_unnormalized(&reversed, TransformType::IV)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::Rng;
+    use rstest::rstest;
+
+    fn naive_dct(x: &DVector<f64>, transform_type: TransformType) -> DVector<f64> {
+        let n = x.len();
+        let nf = n as f64;
+        DVector::from_fn(n, |k, _| {
+            let k = k as f64;
+            match transform_type {
+                TransformType::I => {
+                    x[0] + (-1.0_f64).powf(k) * x[n - 1]
+                        + (1..n - 1)
+                            .map(|i| 2.0 * x[i] * (PI * k * i as f64 / (nf - 1.0)).cos())
+                            .sum::<f64>()
+                }
+                TransformType::II => (0..n)
+                    .map(|i| 2.0 * x[i] * (PI * k * (2.0 * i as f64 + 1.0) / (2.0 * nf)).cos())
+                    .sum(),
+                TransformType::III => {
+                    x[0] + (1..n)
+                        .map(|i| 2.0 * x[i] * (PI * (2.0 * k + 1.0) * i as f64 / (2.0 * nf)).cos())
+                        .sum::<f64>()
+                }
+                TransformType::IV => (0..n)
+                    .map(|i| {
+                        2.0 * x[i]
+                            * (PI * (2.0 * k + 1.0) * (2.0 * i as f64 + 1.0) / (4.0 * nf)).cos()
+                    })
+                    .sum(),
+            }
+        })
+    }
+
+    fn naive_dst(x: &DVector<f64>, transform_type: TransformType) -> DVector<f64> {
+        let n = x.len();
+        let nf = n as f64;
+        DVector::from_fn(n, |k, _| {
+            let k = k as f64;
+            match transform_type {
+                TransformType::I => (0..n)
+                    .map(|i| 2.0 * x[i] * (PI * (k + 1.0) * (i as f64 + 1.0) / (nf + 1.0)).sin())
+                    .sum(),
+                TransformType::II => (0..n)
+                    .map(|i| {
+                        2.0 * x[i] * (PI * (k + 1.0) * (2.0 * i as f64 + 1.0) / (2.0 * nf)).sin()
+                    })
+                    .sum(),
+                TransformType::III => {
+                    (-1.0_f64).powf(k) * x[n - 1]
+                        + (0..n - 1)
+                            .map(|i| {
+                                2.0 * x[i]
+                                    * (PI * (2.0 * k + 1.0) * (i as f64 + 1.0) / (2.0 * nf)).sin()
+                            })
+                            .sum::<f64>()
+                }
+                TransformType::IV => (0..n)
+                    .map(|i| {
+                        2.0 * x[i]
+                            * (PI * (2.0 * k + 1.0) * (2.0 * i as f64 + 1.0) / (4.0 * nf)).sin()
+                    })
+                    .sum(),
+            }
+        })
+    }
+
+    #[rstest]
+    fn test_dct_dst_match_reference(
+        #[values(
+            TransformType::I,
+            TransformType::II,
+            TransformType::III,
+            TransformType::IV
+        )]
+        transform_type: TransformType,
+        #[values(2, 5, 8, 33)] n: usize,
+    ) {
+        let mut rng = rand::thread_rng();
+        let x = DVector::<f64>::from_fn(n, |_, _| rng.gen());
+
+        assert_relative_eq!(
+            dct(&x, transform_type, FftNorm::Backward),
+            naive_dct(&x, transform_type),
+            epsilon = 1e-10
+        );
+        assert_relative_eq!(
+            dst(&x, transform_type, FftNorm::Backward),
+            naive_dst(&x, transform_type),
+            epsilon = 1e-10
+        );
+    }
+
+    #[rstest]
+    fn test_dct_dst_inverse(
+        #[values(
+            TransformType::I,
+            TransformType::II,
+            TransformType::III,
+            TransformType::IV
+        )]
+        transform_type: TransformType,
+        #[values(FftNorm::Backward, FftNorm::Ortho, FftNorm::Forward)] norm: FftNorm,
+    ) {
+        let mut rng = rand::thread_rng();
+        let x = DVector::<f64>::from_fn(12, |_, _| rng.gen());
+
+        assert_relative_eq!(
+            idct(&dct(&x, transform_type, norm), transform_type, norm),
+            x,
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            idst(&dst(&x, transform_type, norm), transform_type, norm),
+            x,
+            epsilon = 1e-12
+        );
+    }
+
+    #[rstest]
+    fn test_dct_dst_ortho_preserves_energy(
+        #[values(
+            TransformType::I,
+            TransformType::II,
+            TransformType::III,
+            TransformType::IV
+        )]
+        transform_type: TransformType,
+    ) {
+        let mut rng = rand::thread_rng();
+        let x = DVector::<f64>::from_fn(16, |_, _| rng.gen());
+
+        assert_relative_eq!(
+            dct(&x, transform_type, FftNorm::Ortho).norm(),
+            x.norm(),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            dst(&x, transform_type, FftNorm::Ortho).norm(),
+            x.norm(),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_dct_forward_norm() {
+        let x = DVector::from_vec(vec![1.0, 2.0, 3.0, 4.0]);
+        assert_relative_eq!(
+            dct(&x, TransformType::II, FftNorm::Forward),
+            dct(&x, TransformType::II, FftNorm::Backward) / 8.0,
+            epsilon = 1e-14
+        );
+        assert_relative_eq!(
+            dct(&x, TransformType::II, FftNorm::Backward)[0],
+            20.0,
+            epsilon = 1e-14
+        );
+    }
+
+    #[rstest]
+    fn test_dct_dst_empty(
+        #[values(
+            TransformType::I,
+            TransformType::II,
+            TransformType::III,
+            TransformType::IV
+        )]
+        transform_type: TransformType,
+        #[values(FftNorm::Backward, FftNorm::Ortho, FftNorm::Forward)] norm: FftNorm,
+    ) {
+        let x = DVector::<f64>::zeros(0);
+        assert_eq!(dct(&x, transform_type, norm).len(), 0);
+        assert_eq!(idct(&x, transform_type, norm).len(), 0);
+        assert_eq!(dst(&x, transform_type, norm).len(), 0);
+        assert_eq!(idst(&x, transform_type, norm).len(), 0);
+    }
+}
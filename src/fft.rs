@@ -1,150 +1,528 @@
+use std::collections::HashMap;
 use std::f64::consts::PI;
 
-use nalgebra::{Complex, DVector, DVectorView, DVectorViewMut, Dyn};
+use nalgebra::{Complex, DMatrix, DVector, Scalar};
 
-pub fn fft(x: &DVector<f64>) -> DVector<Complex<f64>> {
-    let n = x.len();
-    if (n as f64).log2().fract() != 0.0 {
-        panic!("n must be a power of 2");
+// Where the 1/n factor goes: on the inverse (the default), split as 1/sqrt(n) on both
+// directions, or on the forward transform.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FftNorm {
+    #[default]
+    Backward,
+    Ortho,
+    Forward,
+}
+
+impl FftNorm {
+    fn forward_scale(&self, n: usize) -> f64 {
+        match self {
+            FftNorm::Backward => 1.0,
+            FftNorm::Ortho => 1.0 / (n as f64).sqrt(),
+            FftNorm::Forward => 1.0 / n as f64,
+        }
     }
 
-    let w = DVector::from_vec(
-        (0..(n / 2))
-            .map(|i| Complex::new(0.0, -2.0 * i as f64 * PI / n as f64).exp())
-            .collect::<Vec<Complex<f64>>>(),
-    );
+    // Relative to the unnormalized inverse sum
+    fn inverse_scale(&self, n: usize) -> f64 {
+        match self {
+            FftNorm::Backward => 1.0 / n as f64,
+            FftNorm::Ortho => 1.0 / (n as f64).sqrt(),
+            FftNorm::Forward => 1.0,
+        }
+    }
+}
+
+// Precomputed tables and scratch space for transforms of one length. Transforms run in place
+// and do not allocate.
+#[derive(Clone, Debug)]
+pub struct FftPlan {
+    n: usize,
+    norm: FftNorm,
+    algorithm: Algorithm,
+}
+
+#[derive(Clone, Debug)]
+enum Algorithm {
+    Trivial,
+    Radix2 {
+        // exp(-2 pi i j / n) for j in 0..n / 2
+        twiddles: Vec<Complex<f64>>,
+        swaps: Vec<(usize, usize)>,
+    },
+    MixedRadix {
+        // exp(-2 pi i j / n) for j in 0..n
+        twiddles: Vec<Complex<f64>>,
+        factors: Vec<usize>,
+        scratch: Vec<Complex<f64>>,
+    },
+    // Chirp-z transform: the DFT becomes a circular convolution of power-of-two length.
+    Bluestein {
+        chirp: Vec<Complex<f64>>,
+        kernel: Vec<Complex<f64>>,
+        inner: Box<FftPlan>,
+        scratch: Vec<Complex<f64>>,
+    },
+}
+
+// Largest prime handled by a direct butterfly; lengths with longer prime factors go through
+// Bluestein.
+const MAX_RADIX: usize = 13;
+
+impl FftPlan {
+    pub fn new(n: usize) -> Self {
+        let algorithm = if n <= 1 {
+            Algorithm::Trivial
+        } else if n.is_power_of_two() {
+            let width = n.ilog2() as usize;
+            Algorithm::Radix2 {
+                twiddles: twiddles(n, n / 2),
+                swaps: (0..n)
+                    .map(|i| (i, reverse_bits(i, width)))
+                    .filter(|(i, j)| i < j)
+                    .collect(),
+            }
+        } else {
+            let factors = prime_factors(n);
+            if factors.iter().all(|p| *p <= MAX_RADIX) {
+                Algorithm::MixedRadix {
+                    twiddles: twiddles(n, n),
+                    factors,
+                    scratch: vec![Complex::new(0.0, 0.0); n],
+                }
+            } else {
+                let m = (2 * n - 1).next_power_of_two();
+                let mut inner = FftPlan::new(m);
+
+                // k^2 is reduced modulo 2n to keep the phase accurate for long inputs
+                let chirp = (0..n)
+                    .map(|k| Complex::new(0.0, -PI * ((k * k) % (2 * n)) as f64 / n as f64).exp())
+                    .collect::<Vec<_>>();
+                let mut kernel = vec![Complex::new(0.0, 0.0); m];
+                for k in 0..n {
+                    kernel[k] = chirp[k].conj();
+                    if k > 0 {
+                        kernel[m - k] = chirp[k].conj();
+                    }
+                }
+                inner.forward(&mut kernel);
+
+                Algorithm::Bluestein {
+                    chirp,
+                    kernel,
+                    inner: Box::new(inner),
+                    scratch: vec![Complex::new(0.0, 0.0); m],
+                }
+            }
+        };
 
-    let mut x_in = DVector::<Complex<f64>>::zeros(n);
-    let mut x_out = DVector::<Complex<f64>>::zeros(n);
+        Self {
+            n,
+            norm: FftNorm::Backward,
+            algorithm,
+        }
+    }
 
-    for i in 0..n {
-        x_in[i] = Complex::new(x[reverse_bits(i, n.ilog2() as usize)], 0.0);
-        x_out[i] = Complex::new(x_in[i].re, 0.0);
+    pub fn with_norm(mut self, norm: FftNorm) -> Self {
+        self.norm = norm;
+        self
     }
 
-    dft(
-        &mut x_in.rows_mut(0, n / 2),
-        &mut x_out.rows_mut(0, n / 2),
-        &w.rows_with_step(0, w.len() / 2, 1),
-    );
-    dft(
-        &mut x_in.rows_mut(n / 2, n / 2),
-        &mut x_out.rows_mut(n / 2, n / 2),
-        &w.rows_with_step(0, w.len() / 2, 1),
-    );
+    pub fn len(&self) -> usize {
+        self.n
+    }
 
-    for i in 0..(n / 2) {
-        x_out[i] = x_in[i] + w[i] * x_in[(i + n / 2) % n];
-        x_out[i + n / 2] = x_in[i] - w[i] * x_in[(i + n / 2) % n];
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
     }
 
-    x_out
+    pub fn forward(&mut self, x: &mut [Complex<f64>]) {
+        assert_eq!(x.len(), self.n, "The input length must match the plan.");
+        self.transform(x);
+
+        let scale = self.norm.forward_scale(self.n);
+        if scale != 1.0 {
+            x.iter_mut().for_each(|c| *c *= scale);
+        }
+    }
+
+    pub fn inverse(&mut self, x: &mut [Complex<f64>]) {
+        assert_eq!(x.len(), self.n, "The input length must match the plan.");
+
+        // ifft(x) = conj(fft(conj(x))) / n
+        x.iter_mut().for_each(|c| *c = c.conj());
+        self.transform(x);
+        let scale = self.norm.inverse_scale(self.n);
+        x.iter_mut().for_each(|c| *c = c.conj() * scale);
+    }
+
+    fn transform(&mut self, x: &mut [Complex<f64>]) {
+        match &mut self.algorithm {
+            Algorithm::Trivial => {}
+            Algorithm::Radix2 { twiddles, swaps } => radix2(x, twiddles, swaps),
+            Algorithm::MixedRadix {
+                twiddles,
+                factors,
+                scratch,
+            } => {
+                scratch.copy_from_slice(x);
+                mixed_radix(scratch, 1, x, factors, twiddles, 1);
+            }
+            Algorithm::Bluestein {
+                chirp,
+                kernel,
+                inner,
+                scratch,
+            } => {
+                let n = chirp.len();
+                for k in 0..scratch.len() {
+                    scratch[k] = if k < n {
+                        x[k] * chirp[k]
+                    } else {
+                        Complex::new(0.0, 0.0)
+                    };
+                }
+                inner.forward(scratch);
+                for (c, kernel) in scratch.iter_mut().zip(kernel.iter()) {
+                    *c *= kernel;
+                }
+                inner.inverse(scratch);
+                for k in 0..n {
+                    x[k] = scratch[k] * chirp[k];
+                }
+            }
+        }
+    }
+}
+
+// Caches one plan per length.
+#[derive(Clone, Debug, Default)]
+pub struct FftPlanner {
+    plans: HashMap<usize, FftPlan>,
 }
 
-fn dft(
-    x_out: &mut DVectorViewMut<Complex<f64>>,
-    x_in: &mut DVectorViewMut<Complex<f64>>,
-    w: &DVectorView<Complex<f64>, Dyn>,
+impl FftPlanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn plan(&mut self, n: usize) -> &mut FftPlan {
+        self.plans.entry(n).or_insert_with(|| FftPlan::new(n))
+    }
+}
+
+fn twiddles(n: usize, count: usize) -> Vec<Complex<f64>> {
+    (0..count)
+        .map(|j| Complex::new(0.0, -2.0 * PI * j as f64 / n as f64).exp())
+        .collect()
+}
+
+fn radix2(x: &mut [Complex<f64>], twiddles: &[Complex<f64>], swaps: &[(usize, usize)]) {
+    let n = x.len();
+    for (i, j) in swaps.iter() {
+        x.swap(*i, *j);
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        let step = n / len;
+        for start in (0..n).step_by(len) {
+            for k in 0..half {
+                let u = x[start + k];
+                let v = x[start + k + half] * twiddles[k * step];
+                x[start + k] = u + v;
+                x[start + k + half] = u - v;
+            }
+        }
+        len *= 2;
+    }
+}
+
+// Decimation in time: the input is read with the given stride and the sub-transform of length
+// n = output.len() uses every twiddle_stride-th twiddle of the full-length table.
+fn mixed_radix(
+    input: &[Complex<f64>],
+    stride: usize,
+    output: &mut [Complex<f64>],
+    factors: &[usize],
+    twiddles: &[Complex<f64>],
+    twiddle_stride: usize,
 ) {
-    let n = x_in.nrows();
-    if n <= 1 {
+    let n = output.len();
+    if n == 1 {
+        output[0] = input[0];
         return;
     }
 
-    dft(
-        &mut x_in.rows_mut(0, n / 2),
-        &mut x_out.rows_mut(0, n / 2),
-        &w.rows_with_step(0, w.len() / 2, 1),
-    );
-    dft(
-        &mut x_in.rows_mut(n / 2, n / 2),
-        &mut x_out.rows_mut(n / 2, n / 2),
-        &w.rows_with_step(0, w.len() / 2, 1),
-    );
+    let p = factors[0];
+    let m = n / p;
+    for r in 0..p {
+        mixed_radix(
+            &input[r * stride..],
+            stride * p,
+            &mut output[r * m..(r + 1) * m],
+            &factors[1..],
+            twiddles,
+            twiddle_stride * p,
+        );
+    }
 
-    for i in 0..(n / 2) {
-        x_out[i] = x_in[i] + w[i] * x_in[(i + n / 2) % n];
-        x_out[i + n / 2] = x_in[i] - w[i] * x_in[(i + n / 2) % n];
+    // The p-th roots of unity are every m-th twiddle
+    let mut twiddled = [Complex::new(0.0, 0.0); MAX_RADIX];
+    for k in 0..m {
+        for r in 0..p {
+            twiddled[r] = output[r * m + k] * twiddles[r * k * twiddle_stride];
+        }
+        for q in 0..p {
+            output[q * m + k] = (0..p)
+                .map(|r| twiddled[r] * twiddles[(r * q) % p * m * twiddle_stride])
+                .sum();
+        }
     }
 }
 
+fn prime_factors(mut n: usize) -> Vec<usize> {
+    let mut factors = vec![];
+    while n > 1 {
+        let p = smallest_prime_factor(n);
+        factors.push(p);
+        n /= p;
+    }
+    factors
+}
+
+fn smallest_prime_factor(n: usize) -> usize {
+    (2..)
+        .take_while(|p| p * p <= n)
+        .find(|p| n.is_multiple_of(*p))
+        .unwrap_or(n)
+}
+
+pub fn fft(x: &DVector<f64>) -> DVector<Complex<f64>> {
+    fft_complex(&x.map(|v| Complex::new(v, 0.0)))
+}
+
 pub fn ifft(x: &DVector<Complex<f64>>) -> DVector<Complex<f64>> {
+    let mut x = x.clone();
+    FftPlan::new(x.len()).inverse(x.as_mut_slice());
+    x
+}
+
+pub fn fft_complex(x: &DVector<Complex<f64>>) -> DVector<Complex<f64>> {
+    let mut x = x.clone();
+    FftPlan::new(x.len()).forward(x.as_mut_slice());
+    x
+}
+
+// Spectrum of a real signal up to the Nyquist frequency, n / 2 + 1 bins. Even lengths pack the
+// even and odd samples into one complex transform of half the length.
+pub fn rfft(x: &DVector<f64>) -> DVector<Complex<f64>> {
     let n = x.len();
-    if (n as f64).log2().fract() != 0.0 {
-        panic!("n must be a power of 2");
+    if n == 0 {
+        return DVector::zeros(0);
+    }
+    if n % 2 == 1 {
+        return fft(x).rows(0, n / 2 + 1).into_owned();
     }
 
-    let w = DVector::from_vec(
-        (0..(n / 2))
-            .map(|i| Complex::new(0.0, -2.0 * (n - i) as f64 * PI / n as f64).exp())
-            .collect::<Vec<Complex<f64>>>(),
-    );
+    let half = n / 2;
+    let mut z = (0..half)
+        .map(|k| Complex::new(x[2 * k], x[2 * k + 1]))
+        .collect::<Vec<_>>();
+    FftPlan::new(half).forward(&mut z);
 
-    let mut x_in = DVector::<Complex<f64>>::zeros(n);
-    let mut x_out = DVector::<Complex<f64>>::zeros(n);
+    DVector::from_fn(half + 1, |k, _| {
+        let a = z[k % half];
+        let b = z[(half - k) % half].conj();
+        let even = (a + b) * 0.5;
+        let odd = (a - b) * Complex::new(0.0, -0.5);
+        even + Complex::new(0.0, -PI * k as f64 / half as f64).exp() * odd
+    })
+}
 
-    for i in 0..n {
-        x_in[i] = x[i];
-        x_out[i] = x[i];
+// Real signal of length n from its half spectrum. The spectrum is truncated or zero-padded to
+// n / 2 + 1 bins, and the imaginary parts of the DC and Nyquist bins are ignored.
+pub fn irfft(x: &DVector<Complex<f64>>, n: usize) -> DVector<f64> {
+    if n == 0 {
+        return DVector::zeros(0);
     }
+    let bins = n / 2 + 1;
+    let mut spectrum = DVector::from_fn(bins, |k, _| {
+        if k < x.len() {
+            x[k]
+        } else {
+            Complex::new(0.0, 0.0)
+        }
+    });
+    spectrum[0].im = 0.0;
 
-    for i in 0..(n / 2) {
-        x_out[i] = x_in[i] + x_in[i + n / 2];
-        x_out[i + n / 2] = w[i] * (x_in[i] - x_in[i + n / 2]);
+    if n % 2 == 1 {
+        let full = DVector::from_fn(n, |k, _| {
+            if k < bins {
+                spectrum[k]
+            } else {
+                spectrum[n - k].conj()
+            }
+        });
+        return ifft(&full).map(|c| c.re);
     }
+    spectrum[bins - 1].im = 0.0;
 
-    idft(
-        &mut x_in.rows_mut(0, n / 2),
-        &mut x_out.rows_mut(0, n / 2),
-        &w.rows_with_step(0, w.len() / 2, 1),
-    );
-    idft(
-        &mut x_in.rows_mut(n / 2, n / 2),
-        &mut x_out.rows_mut(n / 2, n / 2),
-        &w.rows_with_step(0, w.len() / 2, 1),
-    );
+    // Undo the packing of rfft, then invert the half-length transform
+    let half = n / 2;
+    let mut z = (0..half)
+        .map(|k| {
+            let a = spectrum[k];
+            let b = spectrum[half - k].conj();
+            let even = (a + b) * 0.5;
+            let odd = (a - b) * 0.5 * Complex::new(0.0, PI * k as f64 / half as f64).exp();
+            even + Complex::new(0.0, 1.0) * odd
+        })
+        .collect::<Vec<_>>();
+    FftPlan::new(half).inverse(&mut z);
 
-    let mut result = DVector::<Complex<f64>>::zeros(n);
-    if n.ilog2() % 2 == 0 {
-        for i in 0..n {
-            result[reverse_bits(i, n.ilog2() as usize)] = x_in[i];
-        }
-    } else {
-        for i in 0..n {
-            result[reverse_bits(i, n.ilog2() as usize)] = x_out[i];
-        }
+    DVector::from_fn(n, |i, _| if i % 2 == 0 { z[i / 2].re } else { z[i / 2].im })
+}
+
+pub fn fft_with_norm(x: &DVector<f64>, norm: FftNorm) -> DVector<Complex<f64>> {
+    fft(x) * Complex::new(norm.forward_scale(x.len()), 0.0)
+}
+
+pub fn fft_complex_with_norm(x: &DVector<Complex<f64>>, norm: FftNorm) -> DVector<Complex<f64>> {
+    fft_complex(x) * Complex::new(norm.forward_scale(x.len()), 0.0)
+}
+
+pub fn ifft_with_norm(x: &DVector<Complex<f64>>, norm: FftNorm) -> DVector<Complex<f64>> {
+    let n = x.len();
+    ifft(x) * Complex::new(n as f64 * norm.inverse_scale(n), 0.0)
+}
+
+pub fn rfft_with_norm(x: &DVector<f64>, norm: FftNorm) -> DVector<Complex<f64>> {
+    rfft(x) * Complex::new(norm.forward_scale(x.len()), 0.0)
+}
+
+pub fn irfft_with_norm(x: &DVector<Complex<f64>>, n: usize, norm: FftNorm) -> DVector<f64> {
+    irfft(x, n) * (n as f64 * norm.inverse_scale(n))
+}
+
+pub fn fft2(x: &DMatrix<Complex<f64>>) -> DMatrix<Complex<f64>> {
+    let mut x = x.clone();
+    transform_matrix(&mut x, false);
+    x
+}
+
+pub fn ifft2(x: &DMatrix<Complex<f64>>) -> DMatrix<Complex<f64>> {
+    let mut x = x.clone();
+    transform_matrix(&mut x, true);
+    x
+}
+
+// Spectrum of a real matrix with the columns cut to ncols / 2 + 1, as rfft does along each row.
+pub fn rfft2(x: &DMatrix<f64>) -> DMatrix<Complex<f64>> {
+    let bins = x.ncols() / 2 + 1;
+    let mut spectrum = DMatrix::zeros(x.nrows(), bins);
+    for i in 0..x.nrows() {
+        let row = rfft(&x.row(i).transpose());
+        spectrum.row_mut(i).copy_from(&row.transpose());
     }
 
-    result = result.map(|c| c / (n as f64));
+    let mut plan = FftPlan::new(x.nrows());
+    for mut column in spectrum.column_iter_mut() {
+        plan.forward(column.as_mut_slice());
+    }
+    spectrum
+}
 
+// Real matrix with ncols columns from the spectrum of rfft2.
+pub fn irfft2(x: &DMatrix<Complex<f64>>, ncols: usize) -> DMatrix<f64> {
+    let mut spectrum = x.clone();
+    let mut plan = FftPlan::new(x.nrows());
+    for mut column in spectrum.column_iter_mut() {
+        plan.inverse(column.as_mut_slice());
+    }
+
+    let mut result = DMatrix::zeros(x.nrows(), ncols);
+    for i in 0..x.nrows() {
+        let row = irfft(&spectrum.row(i).transpose(), ncols);
+        result.row_mut(i).copy_from(&row.transpose());
+    }
     result
 }
 
-fn idft(
-    x_out: &mut DVectorViewMut<Complex<f64>>,
-    x_in: &mut DVectorViewMut<Complex<f64>>,
-    w: &DVectorView<Complex<f64>, Dyn>,
-) {
-    let n = x_in.nrows();
-    if n <= 1 {
-        return;
+fn transform_matrix(x: &mut DMatrix<Complex<f64>>, inverse: bool) {
+    let (nrows, ncols) = x.shape();
+
+    // Columns are contiguous in nalgebra's storage
+    let mut plan = FftPlan::new(nrows);
+    for mut column in x.column_iter_mut() {
+        if inverse {
+            plan.inverse(column.as_mut_slice());
+        } else {
+            plan.forward(column.as_mut_slice());
+        }
     }
 
-    for i in 0..(n / 2) {
-        x_out[i] = x_in[i] + x_in[i + n / 2];
-        x_out[i + n / 2] = w[i] * (x_in[i] - x_in[i + n / 2]);
+    let mut plan = FftPlan::new(ncols);
+    let mut buffer = vec![Complex::new(0.0, 0.0); ncols];
+    for i in 0..nrows {
+        for j in 0..ncols {
+            buffer[j] = x[(i, j)];
+        }
+        if inverse {
+            plan.inverse(&mut buffer);
+        } else {
+            plan.forward(&mut buffer);
+        }
+        for j in 0..ncols {
+            x[(i, j)] = buffer[j];
+        }
     }
+}
 
-    idft(
-        &mut x_in.rows_mut(0, n / 2),
-        &mut x_out.rows_mut(0, n / 2),
-        &w.rows_with_step(0, w.len() / 2, 1),
-    );
-    idft(
-        &mut x_in.rows_mut(n / 2, n / 2),
-        &mut x_out.rows_mut(n / 2, n / 2),
-        &w.rows_with_step(0, w.len() / 2, 1),
+// N-dimensional transform of a buffer in row-major order, i.e. the last axis is contiguous.
+pub fn fftn(shape: &[usize], x: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let mut x = x.to_vec();
+    transform_axes(shape, &mut x, false);
+    x
+}
+
+pub fn ifftn(shape: &[usize], x: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let mut x = x.to_vec();
+    transform_axes(shape, &mut x, true);
+    x
+}
+
+fn transform_axes(shape: &[usize], x: &mut [Complex<f64>], inverse: bool) {
+    assert_eq!(
+        shape.iter().product::<usize>(),
+        x.len(),
+        "The buffer length must match the shape."
     );
+
+    for axis in 0..shape.len() {
+        let n = shape[axis];
+        let stride = shape[axis + 1..].iter().product::<usize>();
+        let outer = x.len() / (n * stride).max(1);
+        let mut plan = FftPlan::new(n);
+        let mut buffer = vec![Complex::new(0.0, 0.0); n];
+
+        for o in 0..outer {
+            for s in 0..stride {
+                let offset = o * n * stride + s;
+                for k in 0..n {
+                    buffer[k] = x[offset + k * stride];
+                }
+                if inverse {
+                    plan.inverse(&mut buffer);
+                } else {
+                    plan.forward(&mut buffer);
+                }
+                for k in 0..n {
+                    x[offset + k * stride] = buffer[k];
+                }
+            }
+        }
+    }
 }
 
 pub fn fftfreq(n: usize, dt: f64) -> DVector<f64> {
@@ -161,6 +539,23 @@ pub fn fftfreq(n: usize, dt: f64) -> DVector<f64> {
     result
 }
 
+pub fn rfftfreq(n: usize, dt: f64) -> DVector<f64> {
+    let value = 1.0 / (n as f64 * dt);
+    DVector::from_fn(n / 2 + 1, |i, _| value * i as f64)
+}
+
+// Moves the zero-frequency bin to the centre, matching the order of sorted fftfreq values.
+pub fn fftshift<T: Scalar>(x: &DVector<T>) -> DVector<T> {
+    let n = x.len();
+    DVector::from_fn(n, |i, _| x[(i + n - n / 2) % n].clone())
+}
+
+// Inverse of fftshift, which differs from it for odd lengths.
+pub fn ifftshift<T: Scalar>(x: &DVector<T>) -> DVector<T> {
+    let n = x.len();
+    DVector::from_fn(n, |i, _| x[(i + n / 2) % n].clone())
+}
+
 fn reverse_bits(input: usize, width: usize) -> usize {
     (0..width).fold(0, |acc, i| (acc << 1) | ((input >> i) & 1))
 }
@@ -170,6 +565,7 @@ mod tests {
     use super::*;
     use approx::assert_relative_eq;
     use rand::Rng;
+    use rstest::rstest;
 
     use nalgebra::dvector;
 
@@ -185,13 +581,12 @@ mod tests {
         assert_eq!(reverse_bits(7, 3), 7);
     }
 
-    #[allow(dead_code)]
     fn naive_fft(x: &DVector<f64>) -> DVector<Complex<f64>> {
         let mut result = DVector::<Complex<f64>>::zeros(x.len());
         for i in 0..x.len() {
             for j in 0..x.len() {
                 result[i] += Complex::new(x[j], 0.0)
-                    * Complex::new(0.0, -2.0 * j as f64 * PI / x.len() as f64).exp();
+                    * Complex::new(0.0, -2.0 * (i * j) as f64 * PI / x.len() as f64).exp();
             }
         }
         result
@@ -356,4 +751,488 @@ mod tests {
         assert_relative_eq!(signal_real, original_signal_real, epsilon = 1e-15);
         assert_relative_eq!(signal_imag, original_signal_imag, epsilon = 1e-14);
     }
+
+    fn naive_ifft(x: &DVector<Complex<f64>>) -> DVector<Complex<f64>> {
+        let n = x.len();
+        DVector::from_fn(n, |i, _| {
+            (0..n)
+                .map(|j| x[j] * Complex::new(0.0, 2.0 * (i * j) as f64 * PI / n as f64).exp())
+                .sum::<Complex<f64>>()
+                / n as f64
+        })
+    }
+
+    #[test]
+    fn test_smallest_prime_factor() {
+        assert_eq!(smallest_prime_factor(2), 2);
+        assert_eq!(smallest_prime_factor(15), 3);
+        assert_eq!(smallest_prime_factor(49), 7);
+        assert_eq!(smallest_prime_factor(97), 97);
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(3)]
+    #[case(6)]
+    #[case(12)]
+    #[case(15)]
+    #[case(49)]
+    #[case(17)]
+    #[case(97)]
+    #[case(194)]
+    #[case(1000)]
+    #[case(1009)]
+    #[case(1024)]
+    fn test_fft_any_length(#[case] n: usize) {
+        let mut rng = rand::thread_rng();
+        let signal = DVector::<f64>::from_fn(n, |_, _| rng.gen());
+        let spectrum = fft(&signal);
+        let expected = naive_fft(&signal);
+
+        assert_relative_eq!(
+            spectrum.map(|c| c.re),
+            expected.map(|c| c.re),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            spectrum.map(|c| c.im),
+            expected.map(|c| c.im),
+            epsilon = 1e-9
+        );
+    }
+
+    #[rstest]
+    #[case(5)]
+    #[case(12)]
+    #[case(97)]
+    #[case(1000)]
+    fn test_ifft_any_length(#[case] n: usize) {
+        let mut rng = rand::thread_rng();
+        let spectrum =
+            DVector::<Complex<f64>>::from_fn(n, |_, _| Complex::new(rng.gen(), rng.gen()));
+        let signal = ifft(&spectrum);
+        let expected = naive_ifft(&spectrum);
+
+        assert_relative_eq!(
+            signal.map(|c| c.re),
+            expected.map(|c| c.re),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            signal.map(|c| c.im),
+            expected.map(|c| c.im),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_fft_ifft_loop_any_length() {
+        let n = 48000;
+        let mut rng = rand::thread_rng();
+        let original_signal = DVector::<f64>::from_fn(n, |_, _| rng.gen());
+        let signal = ifft(&fft(&original_signal));
+
+        assert_relative_eq!(signal.map(|c| c.re), original_signal, epsilon = 1e-12);
+        assert_relative_eq!(signal.map(|c| c.im), DVector::zeros(n), epsilon = 1e-12);
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    #[case(8)]
+    #[case(15)]
+    #[case(1000)]
+    #[case(1024)]
+    #[case(48000)]
+    fn test_rfft_matches_fft(#[case] n: usize) {
+        let mut rng = rand::thread_rng();
+        let signal = DVector::<f64>::from_fn(n, |_, _| rng.gen());
+        let spectrum = rfft(&signal);
+        let expected = fft(&signal).rows(0, n / 2 + 1).into_owned();
+
+        assert_eq!(spectrum.len(), n / 2 + 1);
+        assert_relative_eq!(
+            spectrum.map(|c| c.re),
+            expected.map(|c| c.re),
+            epsilon = 1e-8
+        );
+        assert_relative_eq!(
+            spectrum.map(|c| c.im),
+            expected.map(|c| c.im),
+            epsilon = 1e-8
+        );
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    #[case(8)]
+    #[case(15)]
+    #[case(1000)]
+    #[case(1024)]
+    #[case(48000)]
+    fn test_rfft_irfft_loop(#[case] n: usize) {
+        let mut rng = rand::thread_rng();
+        let signal = DVector::<f64>::from_fn(n, |_, _| rng.gen());
+
+        assert_relative_eq!(irfft(&rfft(&signal), n), signal, epsilon = 1e-12);
+        assert_relative_eq!(
+            irfft(&fft(&signal).rows(0, n / 2 + 1).into_owned(), n),
+            ifft(&fft(&signal)).map(|c| c.re),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_irfft_pads_and_truncates() {
+        let spectrum = dvector![Complex::new(4.0, 0.0), Complex::new(0.0, -2.0)];
+        assert_relative_eq!(
+            irfft(&spectrum, 4),
+            dvector![1.0, 2.0, 1.0, 0.0],
+            epsilon = 1e-15
+        );
+        assert_relative_eq!(
+            irfft(
+                &dvector![
+                    Complex::new(3.0, 0.0),
+                    Complex::new(1.0, 0.0),
+                    Complex::new(5.0, 0.0)
+                ],
+                2
+            ),
+            dvector![2.0, 1.0],
+            epsilon = 1e-15
+        );
+    }
+
+    #[test]
+    fn test_rfftfreq() {
+        assert_relative_eq!(rfftfreq(5, 1.0), dvector![0.0, 0.2, 0.4]);
+        assert_relative_eq!(rfftfreq(8, 1.0), dvector![0.0, 0.125, 0.25, 0.375, 0.5]);
+        assert_relative_eq!(
+            rfftfreq(10, 0.2),
+            fftfreq(10, 0.2).rows(0, 6).map(|f| f.abs())
+        );
+    }
+
+    fn naive_fft_complex(x: &DVector<Complex<f64>>) -> DVector<Complex<f64>> {
+        let n = x.len();
+        DVector::from_fn(n, |i, _| {
+            (0..n)
+                .map(|j| x[j] * Complex::new(0.0, -2.0 * (i * j) as f64 * PI / n as f64).exp())
+                .sum::<Complex<f64>>()
+        })
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(7)]
+    #[case(64)]
+    #[case(100)]
+    #[case(97)]
+    fn test_fft_complex(#[case] n: usize) {
+        let mut rng = rand::thread_rng();
+        let signal = DVector::<Complex<f64>>::from_fn(n, |_, _| Complex::new(rng.gen(), rng.gen()));
+        let spectrum = fft_complex(&signal);
+        let expected = naive_fft_complex(&signal);
+
+        assert_relative_eq!(
+            spectrum.map(|c| c.re),
+            expected.map(|c| c.re),
+            epsilon = 1e-10
+        );
+        assert_relative_eq!(
+            spectrum.map(|c| c.im),
+            expected.map(|c| c.im),
+            epsilon = 1e-10
+        );
+
+        let signal_loop = ifft(&spectrum);
+        assert_relative_eq!(
+            signal_loop.map(|c| c.re),
+            signal.map(|c| c.re),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            signal_loop.map(|c| c.im),
+            signal.map(|c| c.im),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_fft_complex_matches_fft_for_real_input() {
+        let signal = dvector![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let spectrum = fft_complex(&signal.map(|v| Complex::new(v, 0.0)));
+        let expected = fft(&signal);
+
+        assert_relative_eq!(
+            spectrum.map(|c| c.re),
+            expected.map(|c| c.re),
+            epsilon = 1e-14
+        );
+        assert_relative_eq!(
+            spectrum.map(|c| c.im),
+            expected.map(|c| c.im),
+            epsilon = 1e-14
+        );
+    }
+
+    #[rstest]
+    #[case(FftNorm::Backward, 1.0)]
+    #[case(FftNorm::Ortho, 1.0 / 10.0_f64.sqrt())]
+    #[case(FftNorm::Forward, 0.1)]
+    fn test_fft_norm(#[case] norm: FftNorm, #[case] scale: f64) {
+        let n = 10;
+        let mut rng = rand::thread_rng();
+        let signal = DVector::<f64>::from_fn(n, |_, _| rng.gen());
+        let complex_signal = signal.map(|v| Complex::new(v, 0.0));
+
+        let spectrum = fft_with_norm(&signal, norm);
+        assert_relative_eq!(
+            spectrum.map(|c| c.re),
+            fft(&signal).map(|c| c.re * scale),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            fft_complex_with_norm(&complex_signal, norm).map(|c| c.im),
+            spectrum.map(|c| c.im),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            rfft_with_norm(&signal, norm).map(|c| c.re),
+            spectrum.rows(0, n / 2 + 1).map(|c| c.re),
+            epsilon = 1e-12
+        );
+
+        // Each norm pairs with its own inverse
+        assert_relative_eq!(
+            ifft_with_norm(&spectrum, norm).map(|c| c.re),
+            signal,
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            irfft_with_norm(&rfft_with_norm(&signal, norm), n, norm),
+            signal,
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_fft_ortho_preserves_energy() {
+        let mut rng = rand::thread_rng();
+        let signal = DVector::<f64>::from_fn(100, |_, _| rng.gen());
+        let spectrum = fft_with_norm(&signal, FftNorm::Ortho);
+
+        assert_relative_eq!(
+            spectrum.map(|c| c.norm_sqr()).sum(),
+            signal.norm_squared(),
+            epsilon = 1e-10
+        );
+    }
+
+    #[test]
+    fn test_fftshift() {
+        assert_relative_eq!(
+            fftshift(&fftfreq(5, 1.0)),
+            dvector![-0.4, -0.2, 0.0, 0.2, 0.4]
+        );
+        assert_relative_eq!(
+            fftshift(&fftfreq(8, 1.0)),
+            dvector![-0.5, -0.375, -0.25, -0.125, 0.0, 0.125, 0.25, 0.375]
+        );
+
+        for n in [5, 8] {
+            let freq = fftfreq(n, 1.0);
+            assert_relative_eq!(ifftshift(&fftshift(&freq)), freq);
+        }
+
+        let spectrum = dvector![
+            Complex::new(0.0, 1.0),
+            Complex::new(1.0, 0.0),
+            Complex::new(2.0, 0.0)
+        ];
+        assert_eq!(
+            ifftshift(&spectrum),
+            dvector![
+                Complex::new(1.0, 0.0),
+                Complex::new(2.0, 0.0),
+                Complex::new(0.0, 1.0)
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(8)]
+    #[case(12)]
+    #[case(97)]
+    fn test_fft_plan(#[case] n: usize) {
+        let mut rng = rand::thread_rng();
+        let mut planner = FftPlanner::new();
+
+        // The same plan is reused across frames
+        for _ in 0..3 {
+            let signal =
+                DVector::<Complex<f64>>::from_fn(n, |_, _| Complex::new(rng.gen(), rng.gen()));
+            let mut buffer = signal.clone();
+            planner.plan(n).forward(buffer.as_mut_slice());
+            let expected = naive_fft_complex(&signal);
+            assert_relative_eq!(
+                buffer.map(|c| c.re),
+                expected.map(|c| c.re),
+                epsilon = 1e-10
+            );
+            assert_relative_eq!(
+                buffer.map(|c| c.im),
+                expected.map(|c| c.im),
+                epsilon = 1e-10
+            );
+
+            planner.plan(n).inverse(buffer.as_mut_slice());
+            assert_relative_eq!(buffer.map(|c| c.re), signal.map(|c| c.re), epsilon = 1e-12);
+            assert_relative_eq!(buffer.map(|c| c.im), signal.map(|c| c.im), epsilon = 1e-12);
+        }
+        assert_eq!(planner.plans.len(), 1);
+        assert_eq!(planner.plan(n).len(), n);
+    }
+
+    #[test]
+    fn test_fft_plan_with_norm() {
+        let signal = dvector![1.0, 2.0, 3.0, 4.0].map(|v| Complex::new(v, 0.0));
+        let mut plan = FftPlan::new(4).with_norm(FftNorm::Ortho);
+
+        let mut buffer = signal.clone();
+        plan.forward(buffer.as_mut_slice());
+        assert_relative_eq!(buffer.map(|c| c.re), dvector![5.0, -1.0, -1.0, -1.0]);
+        assert_relative_eq!(buffer.map(|c| c.im), dvector![0.0, 1.0, 0.0, -1.0]);
+
+        plan.inverse(buffer.as_mut_slice());
+        assert_relative_eq!(buffer.map(|c| c.re), signal.map(|c| c.re));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fft_plan_length_mismatch() {
+        let mut buffer = vec![Complex::new(0.0, 0.0); 8];
+        FftPlan::new(16).forward(&mut buffer);
+    }
+
+    fn naive_fft2(x: &DMatrix<Complex<f64>>) -> DMatrix<Complex<f64>> {
+        let (nrows, ncols) = x.shape();
+        DMatrix::from_fn(nrows, ncols, |k, l| {
+            let mut sum = Complex::new(0.0, 0.0);
+            for i in 0..nrows {
+                for j in 0..ncols {
+                    let phase = (i * k) as f64 / nrows as f64 + (j * l) as f64 / ncols as f64;
+                    sum += x[(i, j)] * Complex::new(0.0, -2.0 * PI * phase).exp();
+                }
+            }
+            sum
+        })
+    }
+
+    #[rstest]
+    #[case(4, 8)]
+    #[case(3, 5)]
+    #[case(6, 1)]
+    fn test_fft2(#[case] nrows: usize, #[case] ncols: usize) {
+        let mut rng = rand::thread_rng();
+        let x = DMatrix::<Complex<f64>>::from_fn(nrows, ncols, |_, _| {
+            Complex::new(rng.gen(), rng.gen())
+        });
+        let spectrum = fft2(&x);
+        let expected = naive_fft2(&x);
+
+        assert_relative_eq!(
+            spectrum.map(|c| c.re),
+            expected.map(|c| c.re),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            spectrum.map(|c| c.im),
+            expected.map(|c| c.im),
+            epsilon = 1e-12
+        );
+
+        let x_loop = ifft2(&spectrum);
+        assert_relative_eq!(x_loop.map(|c| c.re), x.map(|c| c.re), epsilon = 1e-12);
+        assert_relative_eq!(x_loop.map(|c| c.im), x.map(|c| c.im), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_fftn_matches_fft2() {
+        let mut rng = rand::thread_rng();
+        let x = DMatrix::<Complex<f64>>::from_fn(3, 4, |_, _| Complex::new(rng.gen(), rng.gen()));
+
+        // The transpose of a column-major matrix is its row-major buffer
+        let flat = x.transpose().as_slice().to_vec();
+        let spectrum = fftn(&[3, 4], &flat);
+        let expected = fft2(&x).transpose();
+        for (a, b) in spectrum.iter().zip(expected.as_slice().iter()) {
+            assert_relative_eq!(a.re, b.re, epsilon = 1e-12);
+            assert_relative_eq!(a.im, b.im, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_fftn_3d() {
+        let shape = [2, 3, 4];
+        let mut rng = rand::thread_rng();
+        let x = (0..24)
+            .map(|_| Complex::new(rng.gen(), rng.gen()))
+            .collect::<Vec<Complex<f64>>>();
+        let spectrum = fftn(&shape, &x);
+
+        for k0 in 0..2 {
+            for k1 in 0..3 {
+                for k2 in 0..4 {
+                    let mut expected = Complex::new(0.0, 0.0);
+                    for i0 in 0..2 {
+                        for i1 in 0..3 {
+                            for i2 in 0..4 {
+                                let phase = (i0 * k0) as f64 / 2.0
+                                    + (i1 * k1) as f64 / 3.0
+                                    + (i2 * k2) as f64 / 4.0;
+                                expected += x[i0 * 12 + i1 * 4 + i2]
+                                    * Complex::new(0.0, -2.0 * PI * phase).exp();
+                            }
+                        }
+                    }
+                    let value = spectrum[k0 * 12 + k1 * 4 + k2];
+                    assert_relative_eq!(value.re, expected.re, epsilon = 1e-12);
+                    assert_relative_eq!(value.im, expected.im, epsilon = 1e-12);
+                }
+            }
+        }
+
+        let x_loop = ifftn(&shape, &spectrum);
+        for (a, b) in x_loop.iter().zip(x.iter()) {
+            assert_relative_eq!(a.re, b.re, epsilon = 1e-12);
+            assert_relative_eq!(a.im, b.im, epsilon = 1e-12);
+        }
+    }
+
+    #[rstest]
+    #[case(4, 8)]
+    #[case(5, 7)]
+    fn test_rfft2(#[case] nrows: usize, #[case] ncols: usize) {
+        let mut rng = rand::thread_rng();
+        let x = DMatrix::<f64>::from_fn(nrows, ncols, |_, _| rng.gen());
+        let spectrum = rfft2(&x);
+        let expected = fft2(&x.map(|v| Complex::new(v, 0.0)))
+            .columns(0, ncols / 2 + 1)
+            .into_owned();
+
+        assert_relative_eq!(
+            spectrum.map(|c| c.re),
+            expected.map(|c| c.re),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            spectrum.map(|c| c.im),
+            expected.map(|c| c.im),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(irfft2(&spectrum, ncols), x, epsilon = 1e-12);
+    }
 }
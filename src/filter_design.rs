@@ -2,22 +2,38 @@ use std::f64::consts::PI;
 
 use nalgebra::{dvector, stack, Complex, DVector};
 
-use crate::lti::ContinuousTransferFunction;
-use crate::math::{factorial, polynomial};
+use crate::lti::{ContinuousTransferFunction, ContinuousZpk};
+use crate::math::{arc_jacobi_sc1, ellipj, ellipk, ellipkm1, factorial, polynomial, roots};
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum BandType {
     LowPass,
     HighPass,
+    // The band edges replace the `cutoff_freq` argument of the design functions, which must then
+    // be 0 or NaN.
+    BandPass { low: f64, high: f64 },
+    BandStop { low: f64, high: f64 },
 }
 
+// `cutoff_freq` is the -3 dB corner in rad/s of a low- or high-pass filter. Band filters take
+// their edges from `filter_type` instead, and `cutoff_freq` must then be 0 or NaN.
 pub fn design_butter(
     order: usize,
     cutoff_freq: f64,
     filter_type: BandType,
 ) -> ContinuousTransferFunction {
-    let num = match filter_type {
-        BandType::LowPass => dvector![cutoff_freq.powf(order as f64)],
-        BandType::HighPass => stack![dvector![1.0]; DVector::zeros(order)],
+    let high_pass = match filter_type {
+        BandType::LowPass => false,
+        BandType::HighPass => true,
+        BandType::BandPass { .. } | BandType::BandStop { .. } => {
+            return design_butter_zpk(order, cutoff_freq, filter_type).into();
+        }
+    };
+
+    let num = if high_pass {
+        stack![dvector![1.0]; DVector::zeros(order)]
+    } else {
+        dvector![cutoff_freq.powf(order as f64)]
     };
 
     let thetas: Vec<f64> = (1..=order)
@@ -36,43 +52,54 @@ pub fn design_butter(
     ContinuousTransferFunction::new(num, den)
 }
 
+// `cutoff_freq` in rad/s sets the delay to 1 / cutoff_freq for a low-pass filter. It must be 0
+// or NaN for band filters, whose edges come from `filter_type`.
 pub fn design_bessel(
     order: usize,
     cutoff_freq: f64,
     filter_type: BandType,
 ) -> ContinuousTransferFunction {
+    let high_pass = match filter_type {
+        BandType::LowPass => false,
+        BandType::HighPass => true,
+        BandType::BandPass { .. } | BandType::BandStop { .. } => {
+            return design_bessel_zpk(order, cutoff_freq, filter_type).into();
+        }
+    };
+
     let den_bessel = reverse_bessel_polynomial(order);
-    let den_cutoff = match filter_type {
-        BandType::LowPass => DVector::from_vec(
+    let den_cutoff = if high_pass {
+        DVector::from_vec(
             (0..=order)
                 .rev()
-                .map(|k| (1.0 / cutoff_freq).powf(k as f64))
+                .map(|k| (cutoff_freq).powf(k as f64))
                 .collect::<Vec<_>>(),
-        ),
-        BandType::HighPass => DVector::from_vec(
+        )
+    } else {
+        DVector::from_vec(
             (0..=order)
                 .rev()
-                .map(|k| (cutoff_freq).powf(k as f64))
+                .map(|k| (1.0 / cutoff_freq).powf(k as f64))
                 .collect::<Vec<_>>(),
-        ),
+        )
     };
 
-    let den = match filter_type {
-        BandType::LowPass => den_bessel.component_mul(&den_cutoff),
-        BandType::HighPass => DVector::from_vec(
+    let den = if high_pass {
+        DVector::from_vec(
             den_bessel
                 .component_mul(&den_cutoff)
                 .iter()
                 .rev()
                 .cloned()
                 .collect::<Vec<f64>>(),
-        ),
+        )
+    } else {
+        den_bessel.component_mul(&den_cutoff)
     };
-    let num = match filter_type {
-        BandType::LowPass => dvector![den_bessel[den_bessel.len() - 1]],
-        BandType::HighPass => {
-            stack![dvector![den_bessel[den_bessel.len() - 1]]; DVector::zeros(order)]
-        }
+    let num = if high_pass {
+        stack![dvector![den_bessel[den_bessel.len() - 1]]; DVector::zeros(order)]
+    } else {
+        dvector![den_bessel[den_bessel.len() - 1]]
     };
 
     ContinuousTransferFunction::new(num, den)
@@ -90,66 +117,64 @@ fn reverse_bessel_polynomial(order: usize) -> DVector<f64> {
     coeffs
 }
 
+// `cutoff_freq` in rad/s is the passband edge, where the gain leaves the ripple band. It must
+// be 0 or NaN for band filters, whose edges come from `filter_type`.
 pub fn design_chebyshev1(
     order: usize,
     cutoff_freq: f64,
     ripple_db: f64,
     filter_type: BandType,
 ) -> ContinuousTransferFunction {
-    let ripple = f64::sqrt(10.0_f64.powf(ripple_db / 10.0) - 1.0);
-    let mut num = match filter_type {
-        BandType::LowPass => {
-            dvector![cutoff_freq.powf(order as f64) / (2.0_f64.powf(order as f64 - 1.0) * ripple)]
-        }
-        BandType::HighPass => {
-            stack![dvector![1.0 / (2.0_f64.powf(order as f64 - 1.0) * ripple)]; DVector::zeros(order)]
+    let high_pass = match filter_type {
+        BandType::LowPass => false,
+        BandType::HighPass => true,
+        BandType::BandPass { .. } | BandType::BandStop { .. } => {
+            return design_chebyshev1_zpk(order, cutoff_freq, ripple_db, filter_type).into();
         }
     };
 
+    let ripple = f64::sqrt(10.0_f64.powf(ripple_db / 10.0) - 1.0);
+    let mut num = if high_pass {
+        stack![dvector![1.0 / (2.0_f64.powf(order as f64 - 1.0) * ripple)]; DVector::zeros(order)]
+    } else {
+        dvector![cutoff_freq.powf(order as f64) / (2.0_f64.powf(order as f64 - 1.0) * ripple)]
+    };
+
     let mut poles: DVector<Complex<f64>> = DVector::zeros(order);
-    match filter_type {
-        BandType::LowPass => {
-            for k in 1..=order {
-                let theta = (PI / 2.0) * (2.0 * k as f64 - 1.0) / order as f64;
-                poles[k - 1] = cutoff_freq
-                    * Complex::new(
-                        -1.0 * (((1.0 / order as f64) * (1.0 / ripple).asinh()).sinh()
-                            * theta.sin())
-                        .abs(),
-                        ((1.0 / order as f64) * (1.0 / ripple).asinh()).cosh() * theta.cos(),
-                    )
-            }
+    if high_pass {
+        for k in 1..=order {
+            let theta = (PI / 2.0) * (2.0 * k as f64 - 1.0) / order as f64;
+            poles[k - 1] = Complex::new(
+                -(((1.0 / order as f64) * (1.0 / ripple).asinh()).sinh() * theta.sin()).abs(),
+                ((1.0 / order as f64) * (1.0 / ripple).asinh()).cosh() * theta.cos(),
+            )
         }
-        BandType::HighPass => {
-            for k in 1..=order {
-                let theta = (PI / 2.0) * (2.0 * k as f64 - 1.0) / order as f64;
-                poles[k - 1] = Complex::new(
-                    -1.0 * (((1.0 / order as f64) * (1.0 / ripple).asinh()).sinh() * theta.sin())
-                        .abs(),
+        num[0] /= (-poles.clone()).product().re;
+
+        poles = poles.map(|e| e / cutoff_freq);
+    } else {
+        for k in 1..=order {
+            let theta = (PI / 2.0) * (2.0 * k as f64 - 1.0) / order as f64;
+            poles[k - 1] = cutoff_freq
+                * Complex::new(
+                    -(((1.0 / order as f64) * (1.0 / ripple).asinh()).sinh() * theta.sin()).abs(),
                     ((1.0 / order as f64) * (1.0 / ripple).asinh()).cosh() * theta.cos(),
                 )
-            }
-            num[0] /= (-poles.clone()).product().re;
-
-            poles = poles.map(|e| e / cutoff_freq);
         }
     }
-    let den = match filter_type {
-        BandType::LowPass => {
-            DVector::from_vec(polynomial(poles).iter().map(|e| e.re).collect::<Vec<_>>())
-        }
-        BandType::HighPass => {
-            let den = DVector::from_vec(
-                polynomial(poles)
-                    .iter()
-                    .rev()
-                    .map(|e| e.re)
-                    .collect::<Vec<_>>(),
-            );
-            let den_0 = den[0];
+    let den = if high_pass {
+        let den = DVector::from_vec(
+            polynomial(poles)
+                .iter()
+                .rev()
+                .map(|e| e.re)
+                .collect::<Vec<_>>(),
+        );
+        let den_0 = den[0];
 
-            den.map(|e| e / den_0)
-        }
+        den.map(|e| e / den_0)
+    } else {
+        DVector::from_vec(polynomial(poles).iter().map(|e| e.re).collect::<Vec<_>>())
     };
 
     ContinuousTransferFunction::new(num, den)
@@ -173,12 +198,22 @@ pub fn chebyshev1_polynomial(order: usize) -> DVector<f64> {
     }
 }
 
+// `cutoff_freq` in rad/s is the stopband edge, where the attenuation reaches `ripple_db`. It
+// must be 0 or NaN for band filters, whose edges come from `filter_type`.
 pub fn design_chebyshev2(
     order: usize,
     cutoff_freq: f64,
     ripple_db: f64,
     filter_type: BandType,
 ) -> ContinuousTransferFunction {
+    let high_pass = match filter_type {
+        BandType::LowPass => false,
+        BandType::HighPass => true,
+        BandType::BandPass { .. } | BandType::BandStop { .. } => {
+            return design_chebyshev2_zpk(order, cutoff_freq, ripple_db, filter_type).into();
+        }
+    };
+
     let ripple = 1.0 / f64::sqrt(10.0_f64.powf(ripple_db / 10.0) - 1.0);
 
     let mut poles_num_vec: Vec<Complex<f64>> = vec![];
@@ -191,22 +226,19 @@ pub fn design_chebyshev2(
         }
         poles_den[k - 1] = 1.0
             / Complex::new(
-                -1.0 * (((1.0 / order as f64) * (1.0 / ripple).asinh()).sinh() * theta.sin()).abs(),
+                -(((1.0 / order as f64) * (1.0 / ripple).asinh()).sinh() * theta.sin()).abs(),
                 1.0 * ((1.0 / order as f64) * (1.0 / ripple).asinh()).cosh() * theta.cos(),
             );
     }
 
     let mut poles_num = DVector::from_vec(poles_num_vec);
 
-    match filter_type {
-        BandType::LowPass => {
-            poles_den = poles_den.map(|e| e * cutoff_freq);
-            poles_num = poles_num.map(|e| e * cutoff_freq);
-        }
-        BandType::HighPass => {
-            poles_den = poles_den.map(|e| cutoff_freq / e);
-            poles_num = poles_num.map(|e| cutoff_freq / e);
-        }
+    if high_pass {
+        poles_den = poles_den.map(|e| cutoff_freq / e);
+        poles_num = poles_num.map(|e| cutoff_freq / e);
+    } else {
+        poles_den = poles_den.map(|e| e * cutoff_freq);
+        poles_num = poles_num.map(|e| e * cutoff_freq);
     }
 
     let mut num = DVector::from_vec(
@@ -223,31 +255,345 @@ pub fn design_chebyshev2(
     );
 
     // Normalize the numerator
-    match filter_type {
-        BandType::LowPass => {
-            num *= den[den.len() - 1] / num[num.len() - 1];
-        }
-        BandType::HighPass => {
-            num /= num[0];
-            if order % 2 == 1 {
-                num = stack![num; dvector![0.0]];
-            }
+    if high_pass {
+        num /= num[0];
+        if order % 2 == 1 {
+            num = stack![num; dvector![0.0]];
         }
+    } else {
+        num *= den[den.len() - 1] / num[num.len() - 1];
     }
 
     ContinuousTransferFunction::new(num, den)
 }
 
+// `cutoff_freq` in rad/s is the passband edge. It must be 0 or NaN for band filters, whose
+// edges come from `filter_type`.
+pub fn design_ellip(
+    order: usize,
+    cutoff_freq: f64,
+    passband_ripple_db: f64,
+    stopband_atten_db: f64,
+    filter_type: BandType,
+) -> ContinuousTransferFunction {
+    design_ellip_zpk(
+        order,
+        cutoff_freq,
+        passband_ripple_db,
+        stopband_atten_db,
+        filter_type,
+    )
+    .into()
+}
+
+// The zero-pole-gain designs take the same frequency arguments as the transfer function ones.
+pub fn design_butter_zpk(order: usize, cutoff_freq: f64, filter_type: BandType) -> ContinuousZpk {
+    let poles = DVector::from_iterator(
+        order,
+        (1..=order).map(|k| {
+            let theta = PI * (2 * k + order - 1) as f64 / (2 * order) as f64;
+            Complex::new(theta.cos(), theta.sin())
+        }),
+    );
+    let prototype = ContinuousZpk::new(DVector::zeros(0), poles, 1.0);
+
+    transform_low_pass_prototype(&prototype, cutoff_freq, filter_type)
+}
+
+pub fn design_bessel_zpk(order: usize, cutoff_freq: f64, filter_type: BandType) -> ContinuousZpk {
+    let den_bessel = reverse_bessel_polynomial(order);
+    let prototype = ContinuousZpk::new(
+        DVector::zeros(0),
+        roots(&den_bessel),
+        den_bessel[den_bessel.len() - 1],
+    );
+
+    transform_low_pass_prototype(&prototype, cutoff_freq, filter_type)
+}
+
+pub fn design_chebyshev1_zpk(
+    order: usize,
+    cutoff_freq: f64,
+    ripple_db: f64,
+    filter_type: BandType,
+) -> ContinuousZpk {
+    let eps_squared = 10.0_f64.powf(ripple_db / 10.0) - 1.0;
+    let mu = (1.0 / eps_squared.sqrt()).asinh() / order as f64;
+
+    let poles = DVector::from_iterator(
+        order,
+        (1..=order).map(|k| {
+            let theta = (PI / 2.0) * (2.0 * k as f64 - 1.0) / order as f64;
+            Complex::new(-mu.sinh() * theta.sin(), mu.cosh() * theta.cos())
+        }),
+    );
+    let mut gain = poles.iter().map(|p| -p).product::<Complex<f64>>().re;
+    if order.is_multiple_of(2) {
+        gain /= (1.0 + eps_squared).sqrt();
+    }
+    let prototype = ContinuousZpk::new(DVector::zeros(0), poles, gain);
+
+    transform_low_pass_prototype(&prototype, cutoff_freq, filter_type)
+}
+
+pub fn design_chebyshev2_zpk(
+    order: usize,
+    cutoff_freq: f64,
+    ripple_db: f64,
+    filter_type: BandType,
+) -> ContinuousZpk {
+    let eps = 1.0 / (10.0_f64.powf(ripple_db / 10.0) - 1.0).sqrt();
+    let mu = (1.0 / eps).asinh() / order as f64;
+
+    let mut zeros = vec![];
+    let mut poles = vec![];
+    for k in 1..=order {
+        let theta = (PI / 2.0) * (2.0 * k as f64 - 1.0) / order as f64;
+        if (2 * k - 1) != order {
+            zeros.push(Complex::new(0.0, 1.0 / theta.cos()));
+        }
+        poles.push(1.0 / Complex::new(-mu.sinh() * theta.sin(), mu.cosh() * theta.cos()));
+    }
+    let gain = (poles.iter().map(|p| -p).product::<Complex<f64>>()
+        / zeros.iter().map(|z| -z).product::<Complex<f64>>())
+    .re;
+    let prototype = ContinuousZpk::new(DVector::from_vec(zeros), DVector::from_vec(poles), gain);
+
+    transform_low_pass_prototype(&prototype, cutoff_freq, filter_type)
+}
+
+pub fn design_ellip_zpk(
+    order: usize,
+    cutoff_freq: f64,
+    passband_ripple_db: f64,
+    stopband_atten_db: f64,
+    filter_type: BandType,
+) -> ContinuousZpk {
+    let prototype = elliptic_prototype(order, passband_ripple_db, stopband_atten_db);
+
+    transform_low_pass_prototype(&prototype, cutoff_freq, filter_type)
+}
+
+// The elliptic low-pass prototype with its passband edge at 1 rad/s.
+fn elliptic_prototype(
+    order: usize,
+    passband_ripple_db: f64,
+    stopband_atten_db: f64,
+) -> ContinuousZpk {
+    let eps_squared = 10.0_f64.powf(0.1 * passband_ripple_db) - 1.0;
+    if order == 1 {
+        let pole = -(1.0 / eps_squared).sqrt();
+        return ContinuousZpk::new(DVector::zeros(0), dvector![Complex::new(pole, 0.0)], -pole);
+    }
+    let eps = eps_squared.sqrt();
+    let ck1_squared = eps_squared / (10.0_f64.powf(0.1 * stopband_atten_db) - 1.0);
+    let m = elliptic_degree(order, ck1_squared);
+    let capital_k = ellipk(m);
+
+    let jacobi: Vec<(f64, f64, f64)> = ((1 - order % 2)..order)
+        .step_by(2)
+        .map(|j| ellipj(j as f64 * capital_k / order as f64, m))
+        .collect();
+
+    let mut zeros = vec![];
+    for (sn, _, _) in jacobi.iter().filter(|(sn, _, _)| sn.abs() > f64::EPSILON) {
+        zeros.push(Complex::new(0.0, 1.0 / (m.sqrt() * sn)));
+    }
+    zeros.extend(zeros.clone().iter().map(|z| z.conj()));
+
+    let r = arc_jacobi_sc1(1.0 / eps, ck1_squared);
+    let v0 = capital_k * r / (order as f64 * ellipk(ck1_squared));
+    let (sv, cv, dv) = ellipj(v0, 1.0 - m);
+
+    let mut poles: Vec<Complex<f64>> = jacobi
+        .iter()
+        .map(|(s, c, d)| -Complex::new(c * d * sv * cv, s * dv) / (1.0 - (d * sv).powi(2)))
+        .collect();
+    let conjugates: Vec<Complex<f64>> = poles
+        .iter()
+        .filter(|p| p.im.abs() > f64::EPSILON * p.norm())
+        .map(|p| p.conj())
+        .collect();
+    poles.extend(conjugates);
+
+    let mut gain = (poles.iter().map(|p| -p).product::<Complex<f64>>()
+        / zeros.iter().map(|z| -z).product::<Complex<f64>>())
+    .re;
+    if order.is_multiple_of(2) {
+        gain /= (1.0 + eps_squared).sqrt();
+    }
+
+    ContinuousZpk::new(DVector::from_vec(zeros), DVector::from_vec(poles), gain)
+}
+
+// Solves the degree equation for the selectivity parameter using nomes.
+fn elliptic_degree(order: usize, m1: f64) -> f64 {
+    let q1 = (-PI * ellipkm1(m1) / ellipk(m1)).exp();
+    let q = q1.powf(1.0 / order as f64);
+
+    let num: f64 = (0..=7).map(|k| q.powi(k * (k + 1))).sum();
+    let den = 1.0 + 2.0 * (1..=8).map(|k| q.powi(k * k)).sum::<f64>();
+
+    16.0 * q * (num / den).powi(4)
+}
+
+fn transform_low_pass_prototype(
+    prototype: &ContinuousZpk,
+    cutoff_freq: f64,
+    filter_type: BandType,
+) -> ContinuousZpk {
+    assert_unused_cutoff(cutoff_freq, filter_type);
+
+    match filter_type {
+        BandType::LowPass => low_pass_to_low_pass(prototype, cutoff_freq),
+        BandType::HighPass => low_pass_to_high_pass(prototype, cutoff_freq),
+        BandType::BandPass { low, high } => low_pass_to_band_pass(prototype, low, high),
+        BandType::BandStop { low, high } => low_pass_to_band_stop(prototype, low, high),
+    }
+}
+
+// Substitutes s -> s / wc into a low-pass prototype with unit cutoff.
+pub fn low_pass_to_low_pass(prototype: &ContinuousZpk, cutoff_freq: f64) -> ContinuousZpk {
+    let degree = prototype.poles.len() - prototype.zeros.len();
+
+    ContinuousZpk::new(
+        prototype.zeros.map(|z| z * cutoff_freq),
+        prototype.poles.map(|p| p * cutoff_freq),
+        prototype.gain * cutoff_freq.powi(degree as i32),
+    )
+}
+
+// Substitutes s -> wc / s into a low-pass prototype with unit cutoff.
+pub fn low_pass_to_high_pass(prototype: &ContinuousZpk, cutoff_freq: f64) -> ContinuousZpk {
+    let degree = prototype.poles.len() - prototype.zeros.len();
+
+    let zeros = stack![prototype.zeros.map(|z| cutoff_freq / z); DVector::zeros(degree)];
+    let gain = prototype.gain
+        * (prototype.zeros.map(|z| -z).product() / prototype.poles.map(|p| -p).product()).re;
+
+    ContinuousZpk::new(zeros, prototype.poles.map(|p| cutoff_freq / p), gain)
+}
+
+// Substitutes s -> (s^2 + w0^2) / (bw * s) into a low-pass prototype with unit cutoff.
+pub fn low_pass_to_band_pass(prototype: &ContinuousZpk, low: f64, high: f64) -> ContinuousZpk {
+    assert_band_edges(low, high);
+    let w0_squared = low * high;
+    let bandwidth = high - low;
+    let degree = prototype.poles.len() - prototype.zeros.len();
+
+    let transform = |roots: &DVector<Complex<f64>>| {
+        let scaled = roots.map(|r| r * bandwidth / 2.0);
+        let offset = scaled.map(|r| (r * r - w0_squared).sqrt());
+        stack![&scaled + &offset; &scaled - &offset]
+    };
+
+    ContinuousZpk::new(
+        stack![transform(&prototype.zeros); DVector::zeros(degree)],
+        transform(&prototype.poles),
+        prototype.gain * bandwidth.powi(degree as i32),
+    )
+}
+
+// Substitutes s -> (bw * s) / (s^2 + w0^2) into a low-pass prototype with unit cutoff.
+pub fn low_pass_to_band_stop(prototype: &ContinuousZpk, low: f64, high: f64) -> ContinuousZpk {
+    assert_band_edges(low, high);
+    let w0_squared = low * high;
+    let bandwidth = high - low;
+    let degree = prototype.poles.len() - prototype.zeros.len();
+
+    let transform = |roots: &DVector<Complex<f64>>| {
+        let inverted = roots.map(|r| (bandwidth / 2.0) / r);
+        let offset = inverted.map(|r| (r * r - w0_squared).sqrt());
+        stack![&inverted + &offset; &inverted - &offset]
+    };
+    let notch = Complex::new(0.0, w0_squared.sqrt());
+    let gain = prototype.gain
+        * (prototype.zeros.map(|z| -z).product() / prototype.poles.map(|p| -p).product()).re;
+
+    ContinuousZpk::new(
+        stack![
+            transform(&prototype.zeros);
+            DVector::from_element(degree, notch);
+            DVector::from_element(degree, notch.conj())
+        ],
+        transform(&prototype.poles),
+        gain,
+    )
+}
+
+// Band filters take their edges from the band type, so a cutoff passed alongside would be
+// silently ignored.
+pub(crate) fn assert_unused_cutoff(cutoff_freq: f64, filter_type: BandType) {
+    if let BandType::BandPass { .. } | BandType::BandStop { .. } = filter_type {
+        assert!(
+            cutoff_freq == 0.0 || cutoff_freq.is_nan(),
+            "The cutoff frequency is unused for band filters and must be 0 or NaN."
+        );
+    }
+}
+
+// Reversed edges would give a negative bandwidth and poles in the right half-plane.
+fn assert_band_edges(low: f64, high: f64) {
+    assert!(
+        0.0 < low && low < high,
+        "The band edges must satisfy 0 < low < high."
+    );
+}
+
 pub fn digital_to_analog_cutoff(digital_cutoff: f64, sample_rate: f64) -> f64 {
     2.0 * sample_rate * (PI * digital_cutoff / sample_rate).tan()
 }
 
+pub fn analog_to_digital_cutoff(analog_cutoff: f64, sample_rate: f64) -> f64 {
+    sample_rate / PI * (analog_cutoff / (2.0 * sample_rate)).atan()
+}
+
+pub fn digital_to_analog_band(filter_type: BandType, sample_rate: f64) -> BandType {
+    // Prewarping folds edges at or above Nyquist back onto negative frequencies
+    if let BandType::BandPass { high, .. } | BandType::BandStop { high, .. } = filter_type {
+        assert!(
+            high < sample_rate / 2.0,
+            "The band edges must be below the Nyquist frequency."
+        );
+    }
+
+    match filter_type {
+        BandType::BandPass { low, high } => BandType::BandPass {
+            low: digital_to_analog_cutoff(low, sample_rate),
+            high: digital_to_analog_cutoff(high, sample_rate),
+        },
+        BandType::BandStop { low, high } => BandType::BandStop {
+            low: digital_to_analog_cutoff(low, sample_rate),
+            high: digital_to_analog_cutoff(high, sample_rate),
+        },
+        _ => filter_type,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use approx::assert_relative_eq;
     use rstest::rstest;
 
+    use crate::lti::{DiscreteTransferFunction, DiscreteZpk};
+
+    fn evaluate(coeffs: &DVector<f64>, x: Complex<f64>) -> Complex<f64> {
+        coeffs
+            .iter()
+            .fold(Complex::new(0.0, 0.0), |acc, c| acc * x + c)
+    }
+
+    fn analog_gain(tf: &ContinuousTransferFunction, w: f64) -> f64 {
+        let s = Complex::new(0.0, w);
+        (evaluate(&tf.num, s) / evaluate(&tf.den, s)).norm()
+    }
+
+    fn digital_gain(tf: &DiscreteTransferFunction, freq: f64) -> f64 {
+        let z = Complex::new(0.0, 2.0 * PI * freq * tf.dt).exp();
+        (evaluate(&tf.num, z) / evaluate(&tf.den, z)).norm()
+    }
+
     #[rstest]
     #[case(1, 1.0, vec![1.0], vec![1.0, 1.0], 1e-15)]
     #[case(2, 1.0, vec![1.0], vec![1.0, f64::sqrt(2.0), 1.0], 1e-15)]
@@ -389,4 +735,341 @@ mod tests {
         assert_relative_eq!(tf.num, DVector::from_vec(expected_num), epsilon = epsilon);
         assert_relative_eq!(tf.den, DVector::from_vec(expected_den), epsilon = epsilon);
     }
+
+    #[test]
+    fn test_first_order_band_pass_and_band_stop() {
+        let band_pass = design_butter(
+            1,
+            0.0,
+            BandType::BandPass {
+                low: 1.0,
+                high: 4.0,
+            },
+        );
+        assert_relative_eq!(band_pass.num, dvector![3.0, 0.0]);
+        assert_relative_eq!(band_pass.den, dvector![1.0, 3.0, 4.0]);
+
+        let band_stop = design_butter(
+            1,
+            0.0,
+            BandType::BandStop {
+                low: 1.0,
+                high: 4.0,
+            },
+        );
+        assert_relative_eq!(band_stop.num, dvector![1.0, 0.0, 4.0]);
+        assert_relative_eq!(band_stop.den, dvector![1.0, 3.0, 4.0]);
+    }
+
+    #[rstest]
+    #[case(2)]
+    #[case(3)]
+    #[case(4)]
+    fn test_butterworth_band_pass(#[case] order: usize) {
+        let (low, high): (f64, f64) = (10.0, 40.0);
+        let tf = design_butter(order, 0.0, BandType::BandPass { low, high });
+
+        assert_eq!(tf.den.len(), 2 * order + 1);
+        assert_relative_eq!(analog_gain(&tf, (low * high).sqrt()), 1.0, epsilon = 1e-12);
+        assert_relative_eq!(analog_gain(&tf, low), f64::sqrt(0.5), epsilon = 1e-12);
+        assert_relative_eq!(analog_gain(&tf, high), f64::sqrt(0.5), epsilon = 1e-12);
+        assert!(analog_gain(&tf, 1.0) < 1e-2);
+        assert!(analog_gain(&tf, 1000.0) < 1e-2);
+    }
+
+    #[rstest]
+    #[case(2)]
+    #[case(3)]
+    #[case(4)]
+    fn test_butterworth_band_stop(#[case] order: usize) {
+        let (low, high): (f64, f64) = (10.0, 40.0);
+        let tf = design_butter(order, 0.0, BandType::BandStop { low, high });
+
+        assert_eq!(tf.den.len(), 2 * order + 1);
+        assert_relative_eq!(analog_gain(&tf, (low * high).sqrt()), 0.0, epsilon = 1e-9);
+        assert_relative_eq!(analog_gain(&tf, low), f64::sqrt(0.5), epsilon = 1e-12);
+        assert_relative_eq!(analog_gain(&tf, high), f64::sqrt(0.5), epsilon = 1e-12);
+        assert_relative_eq!(analog_gain(&tf, 0.0), 1.0, epsilon = 1e-12);
+        assert_relative_eq!(analog_gain(&tf, 1e5), 1.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_band_pass_other_families() {
+        let (low, high): (f64, f64) = (10.0, 40.0);
+        let center = (low * high).sqrt();
+        let band = BandType::BandPass { low, high };
+
+        let bessel = design_bessel(3, 0.0, band);
+        assert_relative_eq!(analog_gain(&bessel, center), 1.0, epsilon = 1e-12);
+
+        let ripple = 10.0_f64.powf(-1.0 / 20.0);
+        let chebyshev1 = design_chebyshev1(3, 0.0, 1.0, band);
+        assert_relative_eq!(analog_gain(&chebyshev1, low), ripple, epsilon = 1e-12);
+        assert_relative_eq!(analog_gain(&chebyshev1, high), ripple, epsilon = 1e-12);
+
+        let attenuation = 10.0_f64.powf(-40.0 / 20.0);
+        let chebyshev2 = design_chebyshev2(4, 0.0, 40.0, band);
+        assert_relative_eq!(analog_gain(&chebyshev2, center), 1.0, epsilon = 1e-3);
+        assert_relative_eq!(analog_gain(&chebyshev2, low), attenuation, epsilon = 1e-9);
+        assert_relative_eq!(analog_gain(&chebyshev2, high), attenuation, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_band_filters_accept_nan_cutoff() {
+        let band = BandType::BandStop {
+            low: 10.0,
+            high: 40.0,
+        };
+        let tf = design_butter(3, f64::NAN, band);
+        assert_relative_eq!(tf.num, design_butter(3, 0.0, band).num);
+        assert_relative_eq!(tf.den, design_butter(3, 0.0, band).den);
+    }
+
+    #[test]
+    #[should_panic(expected = "The cutoff frequency is unused for band filters")]
+    fn test_band_filters_reject_cutoff() {
+        design_chebyshev1(
+            3,
+            20.0,
+            1.0,
+            BandType::BandPass {
+                low: 10.0,
+                high: 40.0,
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The cutoff frequency is unused for band filters")]
+    fn test_discrete_band_filters_reject_cutoff() {
+        DiscreteTransferFunction::butter(
+            3,
+            100.0,
+            0.001,
+            BandType::BandPass {
+                low: 50.0,
+                high: 200.0,
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The band edges must satisfy 0 < low < high.")]
+    fn test_reversed_band_edges() {
+        design_butter(
+            3,
+            0.0,
+            BandType::BandPass {
+                low: 40.0,
+                high: 10.0,
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The band edges must satisfy 0 < low < high.")]
+    fn test_non_positive_band_edge() {
+        design_chebyshev2_zpk(
+            3,
+            0.0,
+            40.0,
+            BandType::BandStop {
+                low: 0.0,
+                high: 10.0,
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The band edges must be below the Nyquist frequency.")]
+    fn test_band_edge_above_nyquist() {
+        DiscreteTransferFunction::butter(
+            3,
+            0.0,
+            0.001,
+            BandType::BandPass {
+                low: 100.0,
+                high: 500.0,
+            },
+        );
+    }
+
+    #[test]
+    fn test_discrete_band_edges_are_prewarped() {
+        let dt = 1.0 / 1000.0;
+        let (low, high) = (50.0, 200.0);
+
+        let band_pass =
+            DiscreteTransferFunction::butter(3, 0.0, dt, BandType::BandPass { low, high });
+        assert_relative_eq!(
+            digital_gain(&band_pass, low),
+            f64::sqrt(0.5),
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(
+            digital_gain(&band_pass, high),
+            f64::sqrt(0.5),
+            epsilon = 1e-6
+        );
+
+        let band_stop =
+            DiscreteTransferFunction::chebyshev1(2, 0.0, 0.5, dt, BandType::BandStop { low, high });
+        let ripple = 10.0_f64.powf(-0.5 / 20.0);
+        assert_relative_eq!(digital_gain(&band_stop, low), ripple, epsilon = 1e-6);
+        assert_relative_eq!(digital_gain(&band_stop, high), ripple, epsilon = 1e-6);
+        assert_relative_eq!(digital_gain(&band_stop, 0.0), ripple, epsilon = 1e-6);
+    }
+
+    #[rstest]
+    #[case(1, 1.0, 40.0)]
+    #[case(2, 1.0, 40.0)]
+    #[case(3, 0.5, 60.0)]
+    #[case(4, 1.0, 40.0)]
+    #[case(5, 0.1, 80.0)]
+    fn test_elliptic_low_pass(
+        #[case] order: usize,
+        #[case] passband_ripple_db: f64,
+        #[case] stopband_atten_db: f64,
+    ) {
+        let cutoff_freq = 100.0;
+        let tf = design_ellip(
+            order,
+            cutoff_freq,
+            passband_ripple_db,
+            stopband_atten_db,
+            BandType::LowPass,
+        );
+        let passband_gain = 10.0_f64.powf(-passband_ripple_db / 20.0);
+        let stopband_gain = 10.0_f64.powf(-stopband_atten_db / 20.0);
+
+        assert_eq!(tf.den.len(), order + 1);
+        assert_relative_eq!(analog_gain(&tf, cutoff_freq), passband_gain, epsilon = 1e-9);
+        let expected_dc_gain = if order.is_multiple_of(2) {
+            passband_gain
+        } else {
+            1.0
+        };
+        assert_relative_eq!(analog_gain(&tf, 0.0), expected_dc_gain, epsilon = 1e-9);
+
+        let passband = (0..=100).map(|i| analog_gain(&tf, cutoff_freq * i as f64 / 100.0));
+        assert!(passband.fold(f64::INFINITY, f64::min) >= passband_gain - 1e-9);
+
+        if order > 1 {
+            let m = elliptic_degree(order, {
+                let eps_squared = 10.0_f64.powf(0.1 * passband_ripple_db) - 1.0;
+                eps_squared / (10.0_f64.powf(0.1 * stopband_atten_db) - 1.0)
+            });
+            let stopband_edge = cutoff_freq / m.sqrt();
+            assert_relative_eq!(
+                analog_gain(&tf, stopband_edge),
+                stopband_gain,
+                epsilon = 1e-9
+            );
+            let stopband = (0..=1000).map(|i| analog_gain(&tf, stopband_edge * (1.0 + i as f64)));
+            assert!(stopband.fold(0.0, f64::max) <= stopband_gain + 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_elliptic_high_pass_and_band_pass() {
+        let high_pass = design_ellip(4, 100.0, 1.0, 40.0, BandType::HighPass);
+        assert_relative_eq!(
+            analog_gain(&high_pass, 100.0),
+            10.0_f64.powf(-1.0 / 20.0),
+            epsilon = 1e-9
+        );
+        assert!(analog_gain(&high_pass, 10.0) < 1e-2);
+
+        let (low, high): (f64, f64) = (10.0, 40.0);
+        let band_pass = design_ellip(3, 0.0, 0.5, 50.0, BandType::BandPass { low, high });
+        let passband_gain = 10.0_f64.powf(-0.5 / 20.0);
+        assert_eq!(band_pass.den.len(), 7);
+        assert_relative_eq!(analog_gain(&band_pass, low), passband_gain, epsilon = 1e-9);
+        assert_relative_eq!(analog_gain(&band_pass, high), passband_gain, epsilon = 1e-9);
+        assert_relative_eq!(
+            analog_gain(&band_pass, (low * high).sqrt()),
+            1.0,
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn test_discrete_elliptic() {
+        let dt = 1.0 / 1000.0;
+        let tf = DiscreteTransferFunction::ellip(4, 100.0, 1.0, 40.0, dt, BandType::LowPass);
+        let passband_gain = 10.0_f64.powf(-1.0 / 20.0);
+
+        assert_relative_eq!(digital_gain(&tf, 100.0), passband_gain, epsilon = 1e-6);
+        assert_relative_eq!(digital_gain(&tf, 0.0), passband_gain, epsilon = 1e-6);
+        assert!(digital_gain(&tf, 250.0) < 10.0_f64.powf(-40.0 / 20.0) + 1e-6);
+    }
+
+    #[rstest]
+    #[case(BandType::LowPass)]
+    #[case(BandType::HighPass)]
+    fn test_zpk_designs_match_transfer_functions(#[case] filter_type: BandType) {
+        let cases = [
+            (
+                design_butter(4, 10.0, filter_type),
+                design_butter_zpk(4, 10.0, filter_type),
+            ),
+            (
+                design_bessel(4, 10.0, filter_type),
+                design_bessel_zpk(4, 10.0, filter_type),
+            ),
+            (
+                design_chebyshev1(4, 10.0, 1.0, filter_type),
+                design_chebyshev1_zpk(4, 10.0, 1.0, filter_type),
+            ),
+            (
+                design_chebyshev2(3, 10.0, 20.0, filter_type),
+                design_chebyshev2_zpk(3, 10.0, 20.0, filter_type),
+            ),
+        ];
+
+        for (tf, zpk) in cases {
+            let tf_from_zpk = ContinuousTransferFunction::from(zpk);
+            let w_grid = [0.1, 1.0, 5.0, 10.0, 20.0, 100.0];
+            for w in w_grid {
+                assert_relative_eq!(
+                    analog_gain(&tf, w),
+                    analog_gain(&tf_from_zpk, w),
+                    epsilon = 1e-9
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_high_order_band_pass_zpk() {
+        let (low, high): (f64, f64) = (100.0, 110.0);
+        let zpk = design_butter_zpk(12, 0.0, BandType::BandPass { low, high });
+        assert_eq!(zpk.poles.len(), 24);
+        assert_eq!(zpk.zeros.len(), 12);
+
+        let gain = |w: f64| {
+            let s = Complex::new(0.0, w);
+            (zpk.gain * zpk.zeros.map(|z| s - z).product() / zpk.poles.map(|p| s - p).product())
+                .norm()
+        };
+        assert_relative_eq!(gain((low * high).sqrt()), 1.0, epsilon = 1e-9);
+        assert_relative_eq!(gain(low), f64::sqrt(0.5), epsilon = 1e-9);
+        assert_relative_eq!(gain(high), f64::sqrt(0.5), epsilon = 1e-9);
+        assert!(zpk.poles.iter().all(|p| p.re < 0.0));
+    }
+
+    #[test]
+    fn test_discrete_zpk_matches_discrete_transfer_function() {
+        let dt = 1.0 / 1000.0;
+        let band = BandType::BandStop {
+            low: 50.0,
+            high: 150.0,
+        };
+        let tf = DiscreteTransferFunction::chebyshev1(2, 0.0, 1.0, dt, band);
+        let zpk = DiscreteZpk::chebyshev1(2, 0.0, 1.0, dt, band);
+        let tf_from_zpk = DiscreteTransferFunction::from(zpk);
+
+        assert_relative_eq!(tf.num, tf_from_zpk.num, epsilon = 1e-9);
+        assert_relative_eq!(tf.den, tf_from_zpk.den, epsilon = 1e-9);
+    }
 }
@@ -0,0 +1,490 @@
+use crate::filter_design::{analog_to_digital_cutoff, digital_to_analog_cutoff, BandType};
+use crate::lti::DiscreteTransferFunction;
+use crate::math::{ellipk, ellipkm1};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterFamily {
+    Butterworth,
+    Chebyshev1,
+    Chebyshev2,
+    Elliptic,
+}
+
+// The natural frequencies are given as `cutoff_freq` for low/high-pass filters and as the band
+// edges of `filter_type` for band filters, where `cutoff_freq` is 0, so they can be passed
+// straight to the design functions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FilterOrder {
+    pub order: usize,
+    pub cutoff_freq: f64,
+    pub filter_type: BandType,
+}
+
+pub fn butter_order(
+    passband_edges: &[f64],
+    stopband_edges: &[f64],
+    passband_loss_db: f64,
+    stopband_atten_db: f64,
+) -> FilterOrder {
+    estimate_order(
+        FilterFamily::Butterworth,
+        passband_edges,
+        stopband_edges,
+        passband_loss_db,
+        stopband_atten_db,
+    )
+}
+
+pub fn chebyshev1_order(
+    passband_edges: &[f64],
+    stopband_edges: &[f64],
+    passband_loss_db: f64,
+    stopband_atten_db: f64,
+) -> FilterOrder {
+    estimate_order(
+        FilterFamily::Chebyshev1,
+        passband_edges,
+        stopband_edges,
+        passband_loss_db,
+        stopband_atten_db,
+    )
+}
+
+pub fn chebyshev2_order(
+    passband_edges: &[f64],
+    stopband_edges: &[f64],
+    passband_loss_db: f64,
+    stopband_atten_db: f64,
+) -> FilterOrder {
+    estimate_order(
+        FilterFamily::Chebyshev2,
+        passband_edges,
+        stopband_edges,
+        passband_loss_db,
+        stopband_atten_db,
+    )
+}
+
+pub fn ellip_order(
+    passband_edges: &[f64],
+    stopband_edges: &[f64],
+    passband_loss_db: f64,
+    stopband_atten_db: f64,
+) -> FilterOrder {
+    estimate_order(
+        FilterFamily::Elliptic,
+        passband_edges,
+        stopband_edges,
+        passband_loss_db,
+        stopband_atten_db,
+    )
+}
+
+// Estimates the minimal order for analog band edges in rad/s.
+pub fn estimate_order(
+    family: FilterFamily,
+    passband_edges: &[f64],
+    stopband_edges: &[f64],
+    passband_loss_db: f64,
+    stopband_atten_db: f64,
+) -> FilterOrder {
+    assert_eq!(
+        passband_edges.len(),
+        stopband_edges.len(),
+        "The passband and the stopband must have the same number of edges."
+    );
+    assert!(
+        passband_edges.len() == 1 || passband_edges.len() == 2,
+        "The passband must have one or two edges."
+    );
+
+    let gpass = 10.0_f64.powf(0.1 * passband_loss_db.abs());
+    let gstop = 10.0_f64.powf(0.1 * stopband_atten_db.abs());
+    let ratio = (gstop - 1.0) / (gpass - 1.0);
+
+    let mut passband = passband_edges.to_vec();
+    let stopband = stopband_edges;
+
+    let selectivity = if passband.len() == 1 {
+        if passband[0] < stopband[0] {
+            stopband[0] / passband[0]
+        } else {
+            passband[0] / stopband[0]
+        }
+    } else if stopband[0] < passband[0] {
+        band_pass_selectivity(&passband, stopband)
+    } else {
+        let objective = |index: usize| {
+            move |edge: f64| {
+                let mut passband = passband_edges.to_vec();
+                passband[index] = edge;
+                continuous_order(family, band_stop_selectivity(&passband, stopband), ratio)
+            }
+        };
+        passband[0] = minimize_scalar(objective(0), passband[0], stopband[0] - 1e-12);
+        passband[1] = minimize_scalar(objective(1), stopband[1] + 1e-12, passband[1]);
+        band_stop_selectivity(&passband, stopband)
+    };
+
+    let order = continuous_order(family, selectivity, ratio).ceil().max(1.0) as usize;
+
+    let natural_freqs = match family {
+        FilterFamily::Butterworth => {
+            let w0 = (gpass - 1.0).powf(-1.0 / (2.0 * order as f64));
+            natural_freqs(&passband, stopband, w0)
+        }
+        FilterFamily::Chebyshev1 | FilterFamily::Elliptic => passband.clone(),
+        FilterFamily::Chebyshev2 => {
+            let w0 = 1.0 / ((ratio.sqrt().acosh()) / order as f64).cosh();
+            natural_freqs(&passband, stopband, 1.0 / w0)
+        }
+    };
+
+    let (cutoff_freq, filter_type) = if natural_freqs.len() == 1 {
+        if passband[0] < stopband[0] {
+            (natural_freqs[0], BandType::LowPass)
+        } else {
+            (natural_freqs[0], BandType::HighPass)
+        }
+    } else {
+        let (low, high) = (natural_freqs[0], natural_freqs[1]);
+        if stopband[0] < passband[0] {
+            (0.0, BandType::BandPass { low, high })
+        } else {
+            (0.0, BandType::BandStop { low, high })
+        }
+    };
+
+    FilterOrder {
+        order,
+        cutoff_freq,
+        filter_type,
+    }
+}
+
+fn band_pass_selectivity(passband: &[f64], stopband: &[f64]) -> f64 {
+    stopband
+        .iter()
+        .map(|s| ((s * s - passband[0] * passband[1]) / (s * (passband[0] - passband[1]))).abs())
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn band_stop_selectivity(passband: &[f64], stopband: &[f64]) -> f64 {
+    stopband
+        .iter()
+        .map(|s| ((s * (passband[0] - passband[1])) / (s * s - passband[0] * passband[1])).abs())
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn continuous_order(family: FilterFamily, selectivity: f64, ratio: f64) -> f64 {
+    match family {
+        FilterFamily::Butterworth => ratio.log10() / (2.0 * selectivity.log10()),
+        FilterFamily::Chebyshev1 | FilterFamily::Chebyshev2 => {
+            ratio.sqrt().acosh() / selectivity.acosh()
+        }
+        FilterFamily::Elliptic => {
+            let m0 = 1.0 / (selectivity * selectivity);
+            let m1 = 1.0 / ratio;
+            ellipk(m0) * ellipkm1(m1) / (ellipkm1(m0) * ellipk(m1))
+        }
+    }
+}
+
+// Maps the passband edges through the low-pass prototype frequency `w0` of a unit-cutoff design.
+fn natural_freqs(passband: &[f64], stopband: &[f64], w0: f64) -> Vec<f64> {
+    if passband.len() == 1 {
+        if passband[0] < stopband[0] {
+            vec![passband[0] * w0]
+        } else {
+            vec![passband[0] / w0]
+        }
+    } else {
+        let bandwidth = passband[1] - passband[0];
+        let product = passband[0] * passband[1];
+        let mut freqs = if stopband[0] < passband[0] {
+            [-w0, w0]
+                .iter()
+                .map(|w| {
+                    -w * bandwidth / 2.0 + (w * w / 4.0 * bandwidth * bandwidth + product).sqrt()
+                })
+                .collect::<Vec<_>>()
+        } else {
+            let discriminant = (bandwidth * bandwidth + 4.0 * w0 * w0 * product).sqrt();
+            vec![
+                (bandwidth + discriminant) / (2.0 * w0),
+                (bandwidth - discriminant) / (2.0 * w0),
+            ]
+        };
+        freqs = freqs.iter().map(|f| f.abs()).collect();
+        freqs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        freqs
+    }
+}
+
+// Golden-section search for the minimum of a unimodal function on [a, b].
+fn minimize_scalar(f: impl Fn(f64) -> f64, mut a: f64, mut b: f64) -> f64 {
+    let inv_phi = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let mut c = b - inv_phi * (b - a);
+    let mut d = a + inv_phi * (b - a);
+    while (b - a).abs() > 1e-5 {
+        if f(c) < f(d) {
+            b = d;
+        } else {
+            a = c;
+        }
+        c = b - inv_phi * (b - a);
+        d = a + inv_phi * (b - a);
+    }
+
+    (a + b) / 2.0
+}
+
+#[derive(Clone, Debug)]
+pub struct FilterSpec {
+    family: FilterFamily,
+    passband_edges: Vec<f64>,
+    stopband_edges: Vec<f64>,
+    passband_loss_db: f64,
+    stopband_atten_db: f64,
+}
+
+impl FilterSpec {
+    pub fn new(family: FilterFamily) -> Self {
+        Self {
+            family,
+            passband_edges: vec![],
+            stopband_edges: vec![],
+            passband_loss_db: 3.0,
+            stopband_atten_db: 40.0,
+        }
+    }
+
+    pub fn passband(mut self, edges: &[f64]) -> Self {
+        self.passband_edges = edges.to_vec();
+        self
+    }
+
+    pub fn stopband(mut self, edges: &[f64]) -> Self {
+        self.stopband_edges = edges.to_vec();
+        self
+    }
+
+    pub fn passband_loss_db(mut self, passband_loss_db: f64) -> Self {
+        self.passband_loss_db = passband_loss_db;
+        self
+    }
+
+    pub fn stopband_atten_db(mut self, stopband_atten_db: f64) -> Self {
+        self.stopband_atten_db = stopband_atten_db;
+        self
+    }
+
+    // Estimates the order for band edges in Hz, prewarping them for the bilinear transform.
+    pub fn order(&self, dt: f64) -> FilterOrder {
+        let sample_rate = 1.0 / dt;
+        let prewarp = |edges: &[f64]| {
+            edges
+                .iter()
+                .map(|edge| digital_to_analog_cutoff(*edge, sample_rate))
+                .collect::<Vec<_>>()
+        };
+
+        let analog = estimate_order(
+            self.family,
+            &prewarp(&self.passband_edges),
+            &prewarp(&self.stopband_edges),
+            self.passband_loss_db,
+            self.stopband_atten_db,
+        );
+
+        let unwarp = |freq: f64| analog_to_digital_cutoff(freq, sample_rate);
+        let filter_type = match analog.filter_type {
+            BandType::BandPass { low, high } => BandType::BandPass {
+                low: unwarp(low),
+                high: unwarp(high),
+            },
+            BandType::BandStop { low, high } => BandType::BandStop {
+                low: unwarp(low),
+                high: unwarp(high),
+            },
+            filter_type => filter_type,
+        };
+
+        FilterOrder {
+            order: analog.order,
+            cutoff_freq: unwarp(analog.cutoff_freq),
+            filter_type,
+        }
+    }
+
+    pub fn build(&self, dt: f64) -> DiscreteTransferFunction {
+        let FilterOrder {
+            order,
+            cutoff_freq,
+            filter_type,
+        } = self.order(dt);
+
+        match self.family {
+            FilterFamily::Butterworth => {
+                DiscreteTransferFunction::butter(order, cutoff_freq, dt, filter_type)
+            }
+            FilterFamily::Chebyshev1 => DiscreteTransferFunction::chebyshev1(
+                order,
+                cutoff_freq,
+                self.passband_loss_db,
+                dt,
+                filter_type,
+            ),
+            FilterFamily::Chebyshev2 => DiscreteTransferFunction::chebyshev2(
+                order,
+                cutoff_freq,
+                self.stopband_atten_db,
+                dt,
+                filter_type,
+            ),
+            FilterFamily::Elliptic => DiscreteTransferFunction::ellip(
+                order,
+                cutoff_freq,
+                self.passband_loss_db,
+                self.stopband_atten_db,
+                dt,
+                filter_type,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::{Complex, DVector};
+    use rstest::rstest;
+    use std::f64::consts::PI;
+
+    use crate::filter_design::{design_butter, design_chebyshev1, design_chebyshev2, design_ellip};
+    use crate::lti::ContinuousTransferFunction;
+
+    fn evaluate(coeffs: &DVector<f64>, x: Complex<f64>) -> Complex<f64> {
+        coeffs
+            .iter()
+            .fold(Complex::new(0.0, 0.0), |acc, c| acc * x + c)
+    }
+
+    fn analog_gain_db(tf: &ContinuousTransferFunction, w: f64) -> f64 {
+        let s = Complex::new(0.0, w);
+        20.0 * (evaluate(&tf.num, s) / evaluate(&tf.den, s)).norm().log10()
+    }
+
+    fn digital_gain_db(tf: &DiscreteTransferFunction, freq: f64) -> f64 {
+        let z = Complex::new(0.0, 2.0 * PI * freq * tf.dt).exp();
+        20.0 * (evaluate(&tf.num, z) / evaluate(&tf.den, z)).norm().log10()
+    }
+
+    fn design(family: FilterFamily, order: &FilterOrder) -> ContinuousTransferFunction {
+        match family {
+            FilterFamily::Butterworth => {
+                design_butter(order.order, order.cutoff_freq, order.filter_type)
+            }
+            FilterFamily::Chebyshev1 => {
+                design_chebyshev1(order.order, order.cutoff_freq, 1.0, order.filter_type)
+            }
+            FilterFamily::Chebyshev2 => {
+                design_chebyshev2(order.order, order.cutoff_freq, 40.0, order.filter_type)
+            }
+            FilterFamily::Elliptic => {
+                design_ellip(order.order, order.cutoff_freq, 1.0, 40.0, order.filter_type)
+            }
+        }
+    }
+
+    #[test]
+    fn test_butter_order_low_pass() {
+        let result = butter_order(&[1.0], &[2.0], 3.0, 40.0);
+        assert_eq!(result.order, 7);
+        assert_eq!(result.filter_type, BandType::LowPass);
+        assert_relative_eq!(
+            result.cutoff_freq,
+            (10.0_f64.powf(0.3) - 1.0).powf(-1.0 / 14.0),
+            epsilon = 1e-15
+        );
+
+        let result = butter_order(&[2.0], &[1.0], 3.0, 40.0);
+        assert_eq!(result.order, 7);
+        assert_eq!(result.filter_type, BandType::HighPass);
+    }
+
+    #[test]
+    fn test_order_ranking() {
+        let orders = [
+            butter_order(&[1.0], &[1.5], 1.0, 60.0).order,
+            chebyshev1_order(&[1.0], &[1.5], 1.0, 60.0).order,
+            chebyshev2_order(&[1.0], &[1.5], 1.0, 60.0).order,
+            ellip_order(&[1.0], &[1.5], 1.0, 60.0).order,
+        ];
+        assert!(orders[0] > orders[1]);
+        assert_eq!(orders[1], orders[2]);
+        assert!(orders[2] > orders[3]);
+    }
+
+    #[rstest]
+    #[case(FilterFamily::Butterworth, vec![100.0], vec![200.0])]
+    #[case(FilterFamily::Butterworth, vec![200.0], vec![100.0])]
+    #[case(FilterFamily::Butterworth, vec![20.0, 50.0], vec![14.0, 60.0])]
+    #[case(FilterFamily::Butterworth, vec![14.0, 60.0], vec![20.0, 50.0])]
+    #[case(FilterFamily::Chebyshev1, vec![100.0], vec![200.0])]
+    #[case(FilterFamily::Chebyshev1, vec![200.0], vec![100.0])]
+    #[case(FilterFamily::Chebyshev1, vec![20.0, 50.0], vec![14.0, 60.0])]
+    #[case(FilterFamily::Chebyshev1, vec![14.0, 60.0], vec![20.0, 50.0])]
+    #[case(FilterFamily::Chebyshev2, vec![100.0], vec![200.0])]
+    #[case(FilterFamily::Chebyshev2, vec![200.0], vec![100.0])]
+    #[case(FilterFamily::Chebyshev2, vec![20.0, 50.0], vec![14.0, 60.0])]
+    #[case(FilterFamily::Chebyshev2, vec![14.0, 60.0], vec![20.0, 50.0])]
+    #[case(FilterFamily::Elliptic, vec![100.0], vec![200.0])]
+    #[case(FilterFamily::Elliptic, vec![200.0], vec![100.0])]
+    #[case(FilterFamily::Elliptic, vec![20.0, 50.0], vec![14.0, 60.0])]
+    #[case(FilterFamily::Elliptic, vec![14.0, 60.0], vec![20.0, 50.0])]
+    fn test_estimated_order_meets_spec(
+        #[case] family: FilterFamily,
+        #[case] passband_edges: Vec<f64>,
+        #[case] stopband_edges: Vec<f64>,
+    ) {
+        let order = estimate_order(family, &passband_edges, &stopband_edges, 1.0, 40.0);
+        let tf = design(family, &order);
+
+        for edge in passband_edges.iter() {
+            assert!(analog_gain_db(&tf, *edge) >= -1.0 - 1e-6);
+        }
+        for edge in stopband_edges.iter() {
+            assert!(analog_gain_db(&tf, *edge) <= -40.0 + 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_filter_spec_build() {
+        let dt = 1.0 / 1000.0;
+        let spec = FilterSpec::new(FilterFamily::Elliptic)
+            .passband(&[100.0])
+            .stopband(&[150.0])
+            .passband_loss_db(0.5)
+            .stopband_atten_db(60.0);
+
+        let order = spec.order(dt);
+        assert_eq!(order.filter_type, BandType::LowPass);
+        assert_relative_eq!(order.cutoff_freq, 100.0, epsilon = 1e-9);
+
+        let tf = spec.build(dt);
+        assert_eq!(tf.den.len(), order.order + 1);
+        assert!(digital_gain_db(&tf, 100.0) >= -0.5 - 1e-6);
+        assert!(digital_gain_db(&tf, 150.0) <= -60.0 + 1e-6);
+
+        let tf = FilterSpec::new(FilterFamily::Butterworth)
+            .passband(&[100.0, 200.0])
+            .stopband(&[50.0, 300.0])
+            .build(dt);
+        assert!(digital_gain_db(&tf, 100.0) >= -3.0 - 1e-6);
+        assert!(digital_gain_db(&tf, 200.0) >= -3.0 - 1e-6);
+        assert!(digital_gain_db(&tf, 50.0) <= -40.0 + 1e-6);
+        assert!(digital_gain_db(&tf, 300.0) <= -40.0 + 1e-6);
+    }
+}
@@ -0,0 +1,594 @@
+use std::f64::consts::PI;
+
+use nalgebra::{stack, Complex, DMatrix, DVector};
+
+use crate::fft::ifft;
+use crate::filter_design::{assert_unused_cutoff, BandType};
+use crate::freq_response::{discrete_freqs, polyval_inverse};
+use crate::lti::LTI;
+use crate::math::sinc;
+use crate::remez::{remez, RemezError, RemezMode};
+use crate::signal_extension::anti_symmetric_reflect_extension;
+use crate::window::Window;
+
+#[derive(Clone, Debug)]
+pub struct DiscreteFir {
+    pub taps: DVector<f64>,
+    inputs: DVector<f64>,
+    pub dt: f64,
+}
+
+impl DiscreteFir {
+    pub fn new(taps: DVector<f64>, dt: f64) -> Self {
+        let inputs = DVector::zeros(taps.len());
+        Self { taps, inputs, dt }
+    }
+
+    pub fn firwin(
+        numtaps: usize,
+        cutoff_freq: f64,
+        window: Window,
+        filter_type: BandType,
+        sample_rate: f64,
+    ) -> Self {
+        firwin(numtaps, cutoff_freq, window, filter_type, sample_rate)
+    }
+
+    pub fn remez(
+        numtaps: usize,
+        bands: &[f64],
+        desired: &[f64],
+        weights: &[f64],
+        mode: RemezMode,
+        sample_rate: f64,
+    ) -> Result<Self, RemezError> {
+        remez(numtaps, bands, desired, weights, mode, sample_rate)
+    }
+
+    pub fn firls(
+        numtaps: usize,
+        bands: &[f64],
+        desired: &[f64],
+        weights: &[f64],
+        sample_rate: f64,
+    ) -> Self {
+        firls(numtaps, bands, desired, weights, sample_rate)
+    }
+
+    pub fn firwin2(
+        numtaps: usize,
+        freqs: &[f64],
+        gains: &[f64],
+        window: Window,
+        sample_rate: f64,
+    ) -> Self {
+        firwin2(numtaps, freqs, gains, window, sample_rate)
+    }
+
+    pub fn step(&mut self, input: f64) -> f64 {
+        for i in (1..self.inputs.len()).rev() {
+            self.inputs[i] = self.inputs[i - 1];
+        }
+        self.inputs[0] = input;
+
+        self.taps.dot(&self.inputs)
+    }
+
+    // Complex gain at each angular frequency in rad/s, with z = exp(j w dt).
+    pub fn freq_response(&self, freqs: &DVector<f64>) -> DVector<Complex<f64>> {
+        freqs.map(|w| polyval_inverse(&self.taps, Complex::new(0.0, w * self.dt).exp()))
+    }
+
+    // Linearly spaced angular frequencies from DC to Nyquist.
+    pub fn freq_grid(&self, n: usize) -> DVector<f64> {
+        discrete_freqs(self.dt, n)
+    }
+
+    pub fn reset(&mut self) {
+        LTI::reset(self)
+    }
+
+    pub fn simulate(&mut self, inputs: DVector<f64>, t: DVector<f64>) -> DVector<f64> {
+        LTI::simulate(self, inputs, t)
+    }
+
+    pub fn filtfilt(&mut self, u: &DVector<f64>, t: &DVector<f64>) -> DVector<f64> {
+        LTI::filtfilt(self, u, t)
+    }
+}
+
+impl LTI for DiscreteFir {
+    fn reset(&mut self) {
+        self.inputs = DVector::zeros(self.taps.len());
+    }
+
+    fn simulate(&mut self, inputs: DVector<f64>, _t: DVector<f64>) -> DVector<f64> {
+        inputs.map(|input| self.step(input))
+    }
+
+    fn filtfilt(&mut self, u: &DVector<f64>, t: &DVector<f64>) -> DVector<f64> {
+        // padding
+        let u_extended = anti_symmetric_reflect_extension(u.clone());
+        let dt = t[1] - t[0];
+        let t_extended = stack![t; DVector::from_iterator(t.len() * 2, (1..=t.len() * 2).map(|i| t[0] + i as f64 * dt))];
+
+        // forward filtering
+        self.reset();
+        let y_extended = self.simulate(u_extended, t_extended.clone());
+
+        // backward filtering
+        self.reset();
+        let y_extended = DVector::from_iterator(
+            y_extended.len(),
+            y_extended.as_slice().iter().rev().copied(),
+        );
+        let y_extended = self.simulate(y_extended, t_extended);
+        let y_extended = DVector::from_iterator(
+            y_extended.len(),
+            y_extended.as_slice().iter().rev().copied(),
+        );
+
+        y_extended.rows(u.nrows(), u.nrows()).into_owned()
+    }
+}
+
+// Window-method design. The band edges are in Hz, with `cutoff_freq` 0 or NaN for band filters
+// as in the IIR designs. The taps are scaled to unit gain at DC for
+// low-pass and band-stop filters, at Nyquist for high-pass filters and at the band centre for
+// band-pass filters.
+pub fn firwin(
+    numtaps: usize,
+    cutoff_freq: f64,
+    window: Window,
+    filter_type: BandType,
+    sample_rate: f64,
+) -> DiscreteFir {
+    assert_unused_cutoff(cutoff_freq, filter_type);
+    let nyquist = sample_rate / 2.0;
+    let edges_valid = match filter_type {
+        BandType::LowPass | BandType::HighPass => 0.0 < cutoff_freq && cutoff_freq < nyquist,
+        BandType::BandPass { low, high } | BandType::BandStop { low, high } => {
+            0.0 < low && low < high && high < nyquist
+        }
+    };
+    assert!(
+        edges_valid,
+        "The band edges must be increasing and strictly between 0 and the Nyquist frequency."
+    );
+    let bands = match filter_type {
+        BandType::LowPass => vec![(0.0, cutoff_freq / nyquist)],
+        BandType::HighPass => vec![(cutoff_freq / nyquist, 1.0)],
+        BandType::BandPass { low, high } => vec![(low / nyquist, high / nyquist)],
+        BandType::BandStop { low, high } => vec![(0.0, low / nyquist), (high / nyquist, 1.0)],
+    };
+    assert!(
+        bands.iter().all(|(_, right)| *right < 1.0) || numtaps % 2 == 1,
+        "A filter with a passband at the Nyquist frequency must have an odd number of taps."
+    );
+
+    let alpha = 0.5 * (numtaps - 1) as f64;
+    let m = DVector::from_fn(numtaps, |i, _| i as f64 - alpha);
+
+    let mut taps = DVector::zeros(numtaps);
+    for (left, right) in bands.iter() {
+        taps += m.map(|m| right * sinc(right * m) - left * sinc(left * m));
+    }
+    taps.component_mul_assign(&window.symmetric(numtaps));
+
+    let (left, right) = bands[0];
+    let scale_freq = if left == 0.0 {
+        0.0
+    } else if right == 1.0 {
+        1.0
+    } else {
+        0.5 * (left + right)
+    };
+    let scale = taps.dot(&m.map(|m| (PI * m * scale_freq).cos()));
+
+    DiscreteFir::new(taps / scale, 1.0 / sample_rate)
+}
+
+// Least-squares design of a linear-phase filter. `bands` holds pairs of band edges in Hz,
+// `desired` holds the gains at both edges of each band, and `weights` holds one value per band.
+pub fn firls(
+    numtaps: usize,
+    bands: &[f64],
+    desired: &[f64],
+    weights: &[f64],
+    sample_rate: f64,
+) -> DiscreteFir {
+    assert!(
+        !bands.is_empty() && bands.len().is_multiple_of(2),
+        "The bands must be given as pairs of edges."
+    );
+    assert_eq!(
+        desired.len(),
+        bands.len(),
+        "The desired gains must be given at both edges of each band."
+    );
+    assert_eq!(
+        weights.len(),
+        bands.len() / 2,
+        "There must be one weight per band."
+    );
+    let nyquist = sample_rate / 2.0;
+    assert!(
+        bands.windows(2).all(|pair| pair[0] <= pair[1])
+            && bands[0] >= 0.0
+            && bands[bands.len() - 1] <= nyquist,
+        "The band edges must be increasing and between 0 and the Nyquist frequency."
+    );
+
+    // The amplitude response is a cosine series in w = pi * f, with half-integer frequencies
+    // for an even number of taps.
+    let offset = if numtaps % 2 == 1 { 0.0 } else { 0.5 };
+    let l = numtaps.div_ceil(2);
+    let nu = DVector::from_fn(l, |i, _| i as f64 + offset);
+    let bands = bands.iter().map(|b| b / nyquist).collect::<Vec<_>>();
+
+    // Weighted integral of cos(pi * x * f) over the bands
+    let q = |x: f64| -> f64 {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(k, w)| {
+                let (f1, f2) = (bands[2 * k], bands[2 * k + 1]);
+                w * (f2 * sinc(x * f2) - f1 * sinc(x * f1))
+            })
+            .sum()
+    };
+    let q_matrix = DMatrix::from_fn(l, l, |i, j| 0.5 * (q(nu[i] - nu[j]) + q(nu[i] + nu[j])));
+
+    // Weighted integral of (m * f + c) * cos(pi * x * f) over the bands
+    let b = nu.map(|x| {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(k, w)| {
+                let (f1, f2) = (bands[2 * k], bands[2 * k + 1]);
+                if f2 == f1 {
+                    return 0.0;
+                }
+                let m = (desired[2 * k + 1] - desired[2 * k]) / (f2 - f1);
+                let c = desired[2 * k] - m * f1;
+                let antiderivative = |f: f64| {
+                    if x == 0.0 {
+                        0.5 * m * f * f + c * f
+                    } else {
+                        f * (m * f + c) * sinc(x * f) + m * (PI * x * f).cos() / (PI * x).powi(2)
+                    }
+                };
+                w * (antiderivative(f2) - antiderivative(f1))
+            })
+            .sum::<f64>()
+    });
+
+    let a = q_matrix
+        .lu()
+        .solve(&b)
+        .expect("The least-squares system is singular.");
+
+    let mut taps = DVector::zeros(numtaps);
+    for i in 0..l {
+        let (left, right) = (l - 1 - i, numtaps - l + i);
+        if left == right {
+            taps[left] = a[i];
+        } else {
+            taps[left] = 0.5 * a[i];
+            taps[right] = 0.5 * a[i];
+        }
+    }
+
+    DiscreteFir::new(taps, 1.0 / sample_rate)
+}
+
+// Frequency-sampling design. The gains are interpolated linearly between the (freq, gain)
+// breakpoints, which must start at 0 and end at the Nyquist frequency. A repeated frequency
+// gives a step in the response.
+pub fn firwin2(
+    numtaps: usize,
+    freqs: &[f64],
+    gains: &[f64],
+    window: Window,
+    sample_rate: f64,
+) -> DiscreteFir {
+    let nyquist = sample_rate / 2.0;
+    assert_eq!(
+        freqs.len(),
+        gains.len(),
+        "There must be one gain per frequency."
+    );
+    assert!(
+        freqs.len() >= 2 && freqs[0] == 0.0 && freqs[freqs.len() - 1] == nyquist,
+        "The frequencies must start at 0 and end at the Nyquist frequency."
+    );
+    assert!(
+        freqs.windows(2).all(|pair| pair[0] <= pair[1]),
+        "The frequencies must be non-decreasing."
+    );
+    assert!(
+        numtaps % 2 == 1 || gains[gains.len() - 1] == 0.0,
+        "A filter with an even number of taps must have zero gain at the Nyquist frequency."
+    );
+
+    let mut freqs = freqs.to_vec();
+    let eps = f64::EPSILON * nyquist;
+    for k in 0..freqs.len() - 1 {
+        if freqs[k] == freqs[k + 1] {
+            freqs[k] -= eps;
+            freqs[k + 1] += eps;
+        }
+    }
+
+    let nfreqs = 1 + numtaps.next_power_of_two();
+    let n = 2 * (nfreqs - 1);
+    let delay = 0.5 * (numtaps - 1) as f64;
+    let mut spectrum = DVector::<Complex<f64>>::zeros(n);
+    for i in 0..nfreqs {
+        let x = i as f64 / (nfreqs - 1) as f64;
+        let gain = interpolate(x * nyquist, &freqs, gains);
+        spectrum[i] = Complex::new(0.0, -PI * delay * x).exp() * gain;
+        if i > 0 && i < nfreqs - 1 {
+            spectrum[n - i] = spectrum[i].conj();
+        }
+    }
+    // The Nyquist bin of a real signal is real
+    spectrum[nfreqs - 1] = Complex::new(spectrum[nfreqs - 1].re, 0.0);
+
+    let response = ifft(&spectrum);
+    let taps =
+        DVector::from_fn(numtaps, |i, _| response[i].re).component_mul(&window.symmetric(numtaps));
+
+    DiscreteFir::new(taps, 1.0 / sample_rate)
+}
+
+fn interpolate(x: f64, xs: &[f64], ys: &[f64]) -> f64 {
+    if x <= xs[0] {
+        return ys[0];
+    }
+    for k in 1..xs.len() {
+        if x <= xs[k] {
+            return ys[k - 1] + (ys[k] - ys[k - 1]) * (x - xs[k - 1]) / (xs[k] - xs[k - 1]);
+        }
+    }
+    ys[ys.len() - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lti::DiscreteTransferFunction;
+    use approx::assert_relative_eq;
+    use nalgebra::dvector;
+    use rstest::rstest;
+
+    fn gain(fir: &DiscreteFir, freq: f64) -> f64 {
+        fir.taps
+            .iter()
+            .enumerate()
+            .map(|(k, h)| h * Complex::new(0.0, -2.0 * PI * freq * fir.dt * k as f64).exp())
+            .sum::<Complex<f64>>()
+            .norm()
+    }
+
+    #[test]
+    fn test_firwin_rectangular_low_pass() {
+        let fir = firwin(3, 0.5, Window::Rectangular, BandType::LowPass, 2.0);
+        let side = 1.0 / PI;
+        let scale = 0.5 + 2.0 * side;
+
+        assert_relative_eq!(fir.taps, dvector![side, 0.5, side] / scale, epsilon = 1e-15);
+        assert_relative_eq!(fir.dt, 0.5);
+    }
+
+    #[rstest]
+    #[case(200.0, BandType::LowPass, 0.0, 400.0)]
+    #[case(200.0, BandType::HighPass, 500.0, 50.0)]
+    #[case(0.0, BandType::BandPass { low: 100.0, high: 300.0 }, 200.0, 20.0)]
+    #[case(f64::NAN, BandType::BandStop { low: 100.0, high: 300.0 }, 0.0, 200.0)]
+    fn test_firwin(
+        #[case] cutoff_freq: f64,
+        #[case] filter_type: BandType,
+        #[case] passband_freq: f64,
+        #[case] stopband_freq: f64,
+    ) {
+        let fir = firwin(101, cutoff_freq, Window::Hamming, filter_type, 1000.0);
+
+        let n = fir.taps.len();
+        for i in 0..n {
+            assert_relative_eq!(fir.taps[i], fir.taps[n - 1 - i], epsilon = 1e-15);
+        }
+        assert_relative_eq!(gain(&fir, passband_freq), 1.0, epsilon = 1e-3);
+        assert!(gain(&fir, stopband_freq) < 5e-3);
+    }
+
+    #[test]
+    fn test_firwin_cutoff_is_half_amplitude() {
+        let fir = firwin(
+            201,
+            100.0,
+            Window::Kaiser { beta: 8.0 },
+            BandType::LowPass,
+            1000.0,
+        );
+        assert_relative_eq!(gain(&fir, 100.0), 0.5, epsilon = 1e-3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_firwin_high_pass_needs_odd_taps() {
+        firwin(10, 100.0, Window::Hann, BandType::HighPass, 1000.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The cutoff frequency is unused for band filters")]
+    fn test_firwin_band_rejects_cutoff() {
+        firwin(
+            101,
+            200.0,
+            Window::Hamming,
+            BandType::BandPass {
+                low: 100.0,
+                high: 300.0,
+            },
+            1000.0,
+        );
+    }
+
+    #[rstest]
+    #[case(0.0, BandType::LowPass)]
+    #[case(500.0, BandType::LowPass)]
+    #[case(600.0, BandType::HighPass)]
+    #[case(0.0, BandType::BandPass { low: 300.0, high: 100.0 })]
+    #[case(0.0, BandType::BandStop { low: 0.0, high: 100.0 })]
+    #[case(0.0, BandType::BandPass { low: 100.0, high: 500.0 })]
+    #[should_panic(expected = "The band edges must be increasing")]
+    fn test_firwin_invalid_edges(#[case] cutoff_freq: f64, #[case] filter_type: BandType) {
+        firwin(101, cutoff_freq, Window::Hamming, filter_type, 1000.0);
+    }
+
+    #[test]
+    fn test_filtfilt_discrete_fir() {
+        let sample_rate = 1000;
+        let dt = 1.0 / sample_rate as f64;
+        let t = DVector::from_fn(sample_rate + 1, |i, _| i as f64 * dt);
+        let low_frequency_sin_wave = (2.0 * PI * 5.0 * t.clone()).map(|e| e.sin());
+        let high_frequency_sin_wave = (2.0 * PI * 200.0 * t.clone()).map(|e| e.sin());
+        let x = low_frequency_sin_wave.clone() + high_frequency_sin_wave;
+
+        let mut fir = firwin(
+            61,
+            50.0,
+            Window::Hamming,
+            BandType::LowPass,
+            sample_rate as f64,
+        );
+        let y = fir.filtfilt(&x, &t);
+
+        // Away from the edges only the passband ripple remains
+        assert_relative_eq!(
+            y.rows(100, 801),
+            low_frequency_sin_wave.rows(100, 801),
+            epsilon = 1e-2
+        );
+    }
+
+    #[test]
+    fn test_simulate_discrete_fir() {
+        let mut fir = DiscreteFir::new(dvector![1.0, 2.0, 3.0], 1.0);
+        let y = fir.simulate(dvector![1.0, 0.0, 0.0, 1.0], dvector![0.0, 1.0, 2.0, 3.0]);
+        assert_relative_eq!(y, dvector![1.0, 2.0, 3.0, 1.0]);
+
+        fir.reset();
+        assert_relative_eq!(fir.step(1.0), 1.0);
+    }
+
+    #[rstest]
+    #[case(51)]
+    #[case(50)]
+    fn test_firls_low_pass(#[case] numtaps: usize) {
+        let fir = firls(
+            numtaps,
+            &[0.0, 100.0, 150.0, 500.0],
+            &[1.0, 1.0, 0.0, 0.0],
+            &[1.0, 1.0],
+            1000.0,
+        );
+
+        for i in 0..numtaps {
+            assert_relative_eq!(fir.taps[i], fir.taps[numtaps - 1 - i], epsilon = 1e-12);
+        }
+        for freq in [0.0, 50.0, 90.0] {
+            assert_relative_eq!(gain(&fir, freq), 1.0, epsilon = 1e-2);
+        }
+        for freq in [160.0, 250.0, 400.0] {
+            assert!(gain(&fir, freq) < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_firls_all_pass() {
+        let fir = firls(3, &[0.0, 1.0], &[1.0, 1.0], &[1.0], 2.0);
+        assert_relative_eq!(fir.taps, dvector![0.0, 1.0, 0.0], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_firls_piecewise_linear() {
+        // A correction curve rising from 1 to 2 with a don't-care region before the stopband
+        let fir = firls(
+            101,
+            &[0.0, 0.3, 0.35, 0.5],
+            &[1.0, 2.0, 0.0, 0.0],
+            &[1.0, 1.0],
+            1.0,
+        );
+
+        for freq in [0.05, 0.1, 0.15, 0.2, 0.25] {
+            assert_relative_eq!(gain(&fir, freq), 1.0 + freq / 0.3, epsilon = 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_firwin2_all_pass() {
+        let fir = firwin2(9, &[0.0, 50.0], &[1.0, 1.0], Window::Rectangular, 100.0);
+
+        let mut expected = DVector::zeros(9);
+        expected[4] = 1.0;
+        assert_relative_eq!(fir.taps, expected, epsilon = 1e-12);
+    }
+
+    #[rstest]
+    #[case(101)]
+    #[case(100)]
+    fn test_firwin2_low_pass(#[case] numtaps: usize) {
+        let fir = firwin2(
+            numtaps,
+            &[0.0, 100.0, 100.0, 500.0],
+            &[1.0, 1.0, 0.0, 0.0],
+            Window::Hamming,
+            1000.0,
+        );
+
+        for i in 0..numtaps {
+            assert_relative_eq!(fir.taps[i], fir.taps[numtaps - 1 - i], epsilon = 1e-12);
+        }
+        for freq in [0.0, 50.0, 80.0] {
+            assert_relative_eq!(gain(&fir, freq), 1.0, epsilon = 1e-2);
+        }
+        for freq in [120.0, 250.0, 400.0] {
+            assert!(gain(&fir, freq) < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_firwin2_piecewise_linear() {
+        let freqs = [0.0, 0.1, 0.3, 0.5];
+        let gains = [0.5, 0.5, 1.5, 1.0];
+        let fir = firwin2(201, &freqs, &gains, Window::Hann, 1.0);
+
+        for freq in [0.05, 0.15, 0.2, 0.25, 0.35, 0.45] {
+            assert_relative_eq!(
+                gain(&fir, freq),
+                interpolate(freq, &freqs, &gains),
+                epsilon = 1e-2
+            );
+        }
+    }
+
+    #[test]
+    fn test_least_squares_designs_as_transfer_function() {
+        let fir = firls(
+            21,
+            &[0.0, 0.1, 0.2, 0.5],
+            &[1.0, 1.0, 0.0, 0.0],
+            &[1.0, 1.0],
+            1.0,
+        );
+        let mut tf = DiscreteTransferFunction::from(fir.clone());
+
+        let mut impulse = DVector::zeros(21);
+        impulse[0] = 1.0;
+        let t = DVector::from_fn(21, |i, _| i as f64);
+        assert_relative_eq!(tf.simulate(impulse, t), fir.taps, epsilon = 1e-12);
+    }
+}
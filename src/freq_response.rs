@@ -0,0 +1,473 @@
+use std::f64::consts::PI;
+
+use nalgebra::{Complex, DMatrix, DVector};
+
+// Frequencies here are angular, in rad/s: continuous systems are evaluated at s = j w and
+// discrete ones at z = exp(j w dt).
+
+// n points spaced evenly from start to stop, both included.
+pub fn linear_freqs(start: f64, stop: f64, n: usize) -> DVector<f64> {
+    if n == 1 {
+        return DVector::from_element(1, start);
+    }
+    DVector::from_fn(n, |i, _| start + (stop - start) * i as f64 / (n - 1) as f64)
+}
+
+// n points spaced evenly on a log scale from start to stop, both included.
+pub fn log_freqs(start: f64, stop: f64, n: usize) -> DVector<f64> {
+    assert!(
+        start > 0.0 && stop > 0.0,
+        "A log-spaced grid needs positive bounds."
+    );
+    linear_freqs(start.log10(), stop.log10(), n).map(|e| 10.0_f64.powf(e))
+}
+
+pub fn magnitude_db(response: &DVector<Complex<f64>>) -> DVector<f64> {
+    response.map(|h| 20.0 * h.norm().log10())
+}
+
+// Phase in radians, unwrapped along the frequencies.
+pub fn phase(response: &DVector<Complex<f64>>) -> DVector<f64> {
+    unwrap_phase(&response.map(|h| h.arg()))
+}
+
+// Removes the jumps of 2 pi between consecutive samples, so that no step exceeds pi in
+// magnitude. The first sample is kept as it is.
+pub fn unwrap_phase(phase: &DVector<f64>) -> DVector<f64> {
+    let mut offset = 0.0;
+    let mut unwrapped = phase.clone();
+    for i in 1..unwrapped.len() {
+        let jump = phase[i] + offset - unwrapped[i - 1];
+        offset -= 2.0 * PI * (jump / (2.0 * PI)).round();
+        unwrapped[i] = phase[i] + offset;
+    }
+    unwrapped
+}
+
+// Log-spaced grid over the decades where the given zeros and poles shape the response, as
+// chosen by scipy.signal.findfreqs.
+pub fn continuous_freqs(
+    zeros: &DVector<Complex<f64>>,
+    poles: &DVector<Complex<f64>>,
+    n: usize,
+) -> DVector<f64> {
+    let mut roots = poles
+        .iter()
+        .chain(zeros.iter().filter(|z| z.norm() < 1e5))
+        .filter(|r| r.im >= 0.0)
+        .copied()
+        .collect::<Vec<_>>();
+    if roots.is_empty() {
+        roots.push(Complex::new(-1000.0, 0.0));
+    }
+
+    // Roots at the origin count as 1 rad/s
+    let shifted = roots
+        .iter()
+        .map(|r| if r.norm() < 1e-10 { r + 1.0 } else { *r })
+        .collect::<Vec<_>>();
+    let high = shifted
+        .iter()
+        .map(|r| 3.0 * r.re.abs() + 1.5 * r.im)
+        .fold(f64::MIN, f64::max);
+    let low = shifted
+        .iter()
+        .map(|r| 0.1 * (r.re.abs() + 2.0 * r.im))
+        .fold(f64::MAX, f64::min);
+
+    let high_decade = (high.log10() + 0.5).round();
+    let low_decade = (low.log10() - 0.5).round();
+    linear_freqs(low_decade, high_decade, n).map(|e| 10.0_f64.powf(e))
+}
+
+// n points from DC up to and including the Nyquist frequency pi / dt.
+pub fn discrete_freqs(dt: f64, n: usize) -> DVector<f64> {
+    linear_freqs(0.0, PI / dt, n)
+}
+
+// Polynomial with coefficients in descending powers.
+pub(crate) fn polyval(coeffs: &DVector<f64>, x: Complex<f64>) -> Complex<f64> {
+    coeffs
+        .iter()
+        .fold(Complex::new(0.0, 0.0), |acc, c| acc * x + c)
+}
+
+// Derivative of a polynomial with coefficients in descending powers.
+pub(crate) fn polyder(coeffs: &DVector<f64>) -> DVector<f64> {
+    let degree = coeffs.len().saturating_sub(1);
+    DVector::from_fn(degree, |i, _| coeffs[i] * (degree - i) as f64)
+}
+
+// Whether a polynomial in descending powers, evaluated to `value` at a point of the given
+// magnitude, is zero there up to the rounding of its terms.
+pub(crate) fn vanishes(coeffs: &DVector<f64>, value: Complex<f64>, magnitude: f64) -> bool {
+    let bound = coeffs.iter().fold(0.0, |acc, c| acc * magnitude + c.abs());
+    value.norm() <= 10.0 * f64::EPSILON * bound
+}
+
+// Polynomial in z^-1 with coefficients in ascending powers of z^-1.
+pub(crate) fn polyval_inverse(coeffs: &DVector<f64>, z: Complex<f64>) -> Complex<f64> {
+    let z_inv = z.inv();
+    coeffs
+        .iter()
+        .rev()
+        .fold(Complex::new(0.0, 0.0), |acc, c| acc * z_inv + c)
+}
+
+pub(crate) fn zpk_response(
+    zeros: &DVector<Complex<f64>>,
+    poles: &DVector<Complex<f64>>,
+    gain: f64,
+    x: Complex<f64>,
+) -> Complex<f64> {
+    gain * zeros.iter().map(|z| x - z).product::<Complex<f64>>()
+        / poles.iter().map(|p| x - p).product::<Complex<f64>>()
+}
+
+// C (x I - A)^-1 B + D from the first input to the first output.
+pub(crate) fn state_space_response(
+    a: &DMatrix<f64>,
+    b: &DMatrix<f64>,
+    c: &DMatrix<f64>,
+    d: &DMatrix<f64>,
+    x: Complex<f64>,
+) -> Complex<f64> {
+    let to_complex = |m: &DMatrix<f64>| m.map(|e| Complex::new(e, 0.0));
+    let n = a.nrows();
+    let resolvent = DMatrix::from_diagonal_element(n, n, x) - to_complex(a);
+    // At an eigenvalue of A the resolvent is singular. The gain is then not finite, and NaN
+    // matches the 0 / 0 division of the polynomial and zero-pole forms at a pole.
+    match resolvent
+        .lu()
+        .solve(&to_complex(&b.columns(0, 1).into_owned()))
+    {
+        Some(states) => (to_complex(&c.rows(0, 1).into_owned()) * states)[(0, 0)] + d[(0, 0)],
+        None => Complex::new(f64::NAN, f64::NAN),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter_design::BandType;
+    use crate::fir::DiscreteFir;
+    use crate::lti::{
+        ContinuousStateSpace, ContinuousTransferFunction, ContinuousZpk, DiscreteSos,
+        DiscreteStateSpace, DiscreteTransferFunction, DiscreteZpk,
+    };
+    use crate::math::roots;
+    use crate::window::Window;
+    use approx::assert_relative_eq;
+    use nalgebra::dvector;
+    use rstest::rstest;
+
+    fn assert_responses_eq(a: &DVector<Complex<f64>>, b: &DVector<Complex<f64>>, epsilon: f64) {
+        assert_relative_eq!(a.map(|h| h.re), b.map(|h| h.re), epsilon = epsilon);
+        assert_relative_eq!(a.map(|h| h.im), b.map(|h| h.im), epsilon = epsilon);
+    }
+
+    #[test]
+    fn test_grids() {
+        assert_relative_eq!(
+            linear_freqs(0.0, 1.0, 5),
+            dvector![0.0, 0.25, 0.5, 0.75, 1.0]
+        );
+        assert_relative_eq!(
+            log_freqs(0.1, 100.0, 4),
+            dvector![0.1, 1.0, 10.0, 100.0],
+            max_relative = 1e-14
+        );
+        assert_relative_eq!(discrete_freqs(0.5, 3), dvector![0.0, PI, 2.0 * PI]);
+    }
+
+    #[rstest]
+    #[case(vec![], vec![Complex::new(-0.5_f64.sqrt(), 0.5_f64.sqrt()), Complex::new(-0.5_f64.sqrt(), -0.5_f64.sqrt())], 0.1, 10.0)]
+    #[case(vec![Complex::new(0.0, 0.0)], vec![Complex::new(-100.0, 0.0)], 0.01, 1000.0)]
+    #[case(vec![], vec![], 100.0, 10_000.0)]
+    fn test_continuous_freqs(
+        #[case] zeros: Vec<Complex<f64>>,
+        #[case] poles: Vec<Complex<f64>>,
+        #[case] low: f64,
+        #[case] high: f64,
+    ) {
+        let freqs = continuous_freqs(&DVector::from_vec(zeros), &DVector::from_vec(poles), 50);
+        assert_relative_eq!(freqs[0], low, max_relative = 1e-12);
+        assert_relative_eq!(freqs[49], high, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn test_magnitude_and_phase() {
+        let response = dvector![
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, -0.1),
+            Complex::new(-0.01, 0.0),
+            Complex::new(0.0, 0.001)
+        ];
+        assert_relative_eq!(
+            magnitude_db(&response),
+            dvector![0.0, -20.0, -40.0, -60.0],
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            phase(&response),
+            dvector![0.0, -PI / 2.0, -PI, -3.0 * PI / 2.0],
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_continuous_responses_agree() {
+        let tf = ContinuousTransferFunction::butter(4, 10.0, BandType::LowPass);
+        let zpk = ContinuousZpk::butter(4, 10.0, BandType::LowPass);
+        let state_space = ContinuousStateSpace::from(tf.clone());
+        let freqs = tf.freq_grid(200);
+
+        let response = tf.freq_response(&freqs);
+        assert_responses_eq(&zpk.freq_response(&freqs), &response, 1e-12);
+        assert_responses_eq(&state_space.freq_response(&freqs), &response, 1e-12);
+        assert_relative_eq!(zpk.freq_grid(200), freqs);
+        assert_relative_eq!(state_space.freq_grid(200), freqs);
+
+        // A Butterworth filter is 3 dB down at the cutoff and falls at 20 dB per decade per pole
+        let magnitude = magnitude_db(&tf.freq_response(&dvector![0.0, 10.0, 1000.0]));
+        assert_relative_eq!(magnitude[0], 0.0, epsilon = 1e-12);
+        assert_relative_eq!(magnitude[1], -3.0103, epsilon = 1e-4);
+        assert_relative_eq!(magnitude[2], -160.0, epsilon = 1e-6);
+
+        // The phase falls by pi / 4 per pole, half of it by the cutoff
+        let phase = phase(&tf.freq_response(&log_freqs(0.1, 1000.0, 101)));
+        assert!(phase.as_slice().windows(2).all(|pair| pair[1] < pair[0]));
+        assert_relative_eq!(phase[0], 0.0, epsilon = 0.03);
+        assert_relative_eq!(phase[50], -PI, epsilon = 1e-12);
+        assert_relative_eq!(phase[100], -2.0 * PI, epsilon = 0.03);
+    }
+
+    #[test]
+    fn test_discrete_responses_agree() {
+        let dt = 1.0 / 1000.0;
+        let tf = DiscreteTransferFunction::butter(4, 100.0, dt, BandType::LowPass);
+        let zpk = DiscreteZpk::butter(4, 100.0, dt, BandType::LowPass);
+        let sos = DiscreteSos::butter(4, 100.0, dt, BandType::LowPass);
+        let freqs = tf.freq_grid(101);
+
+        let response = tf.freq_response(&freqs);
+        assert_responses_eq(&zpk.freq_response(&freqs), &response, 1e-10);
+        assert_responses_eq(&sos.freq_response(&freqs), &response, 1e-10);
+        assert_relative_eq!(freqs, discrete_freqs(dt, 101));
+        assert_relative_eq!(zpk.freq_grid(101), freqs);
+        assert_relative_eq!(sos.freq_grid(101), freqs);
+
+        // The bilinear transform keeps the cutoff and sends Nyquist to a zero
+        let magnitude = magnitude_db(&tf.freq_response(&dvector![0.0, 2.0 * PI * 100.0]));
+        assert_relative_eq!(magnitude[0], 0.0, epsilon = 1e-9);
+        assert_relative_eq!(magnitude[1], -3.0103, epsilon = 1e-3);
+        assert!(response[100].norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_discrete_state_space_and_fir() {
+        let dt = 0.1;
+        let state_space = DiscreteStateSpace::new(
+            DMatrix::from_element(1, 1, 0.5),
+            DMatrix::from_element(1, 1, 1.0),
+            DMatrix::from_element(1, 1, 1.0),
+            DMatrix::from_element(1, 1, 0.0),
+            dt,
+        );
+        let fir = DiscreteFir::new(dvector![0.25, 0.5, 0.25], dt);
+        let freqs = state_space.freq_grid(11);
+        assert_relative_eq!(fir.freq_grid(11), freqs);
+
+        // 1 / (z - 0.5) and 0.25 (1 + z^-1)^2
+        let expected = freqs.map(|w| {
+            let z = Complex::new(0.0, w * dt).exp();
+            (1.0 / (z - 0.5), 0.25 * (1.0 + z.inv()).powi(2))
+        });
+        assert_responses_eq(
+            &state_space.freq_response(&freqs),
+            &expected.map(|e| e.0),
+            1e-12,
+        );
+        assert_responses_eq(&fir.freq_response(&freqs), &expected.map(|e| e.1), 1e-12);
+    }
+
+    #[test]
+    fn test_integrator_on_its_own_grid() {
+        let (a, b, c, d) = (
+            DMatrix::from_element(1, 1, 0.0),
+            DMatrix::from_element(1, 1, 1.0),
+            DMatrix::from_element(1, 1, 1.0),
+            DMatrix::from_element(1, 1, 0.0),
+        );
+        let state_space = ContinuousStateSpace::new(a.clone(), b.clone(), c.clone(), d.clone());
+        let freqs = state_space.freq_grid(5);
+        assert_responses_eq(
+            &state_space.freq_response(&freqs),
+            &freqs.map(|w| 1.0 / Complex::new(0.0, w)),
+            1e-12,
+        );
+
+        // The grid of a discrete accumulator starts at its pole, z = 1
+        let dt = 0.1;
+        let accumulator = DiscreteStateSpace::new(DMatrix::from_element(1, 1, 1.0), b, c, d, dt);
+        let freqs = accumulator.freq_grid(5);
+        let response = accumulator.freq_response(&freqs);
+        assert_responses_eq(
+            &response.rows(1, 4).into_owned(),
+            &freqs
+                .rows(1, 4)
+                .map(|w| 1.0 / (Complex::new(0.0, w * dt).exp() - 1.0)),
+            1e-12,
+        );
+
+        // Every form gives NaN at a pole
+        let at_pole = [
+            response[0],
+            state_space.freq_response(&dvector![0.0])[0],
+            ContinuousTransferFunction::new(dvector![1.0], dvector![1.0, 0.0])
+                .freq_response(&dvector![0.0])[0],
+            ContinuousZpk::new(dvector![], dvector![Complex::new(0.0, 0.0)], 1.0)
+                .freq_response(&dvector![0.0])[0],
+            DiscreteTransferFunction::new(dvector![0.0, 1.0], dvector![1.0, -1.0], dt)
+                .freq_response(&dvector![0.0])[0],
+            DiscreteZpk::new(dvector![], dvector![Complex::new(1.0, 0.0)], 1.0, dt)
+                .freq_response(&dvector![0.0])[0],
+        ];
+        assert!(at_pole.iter().all(|h| h.re.is_nan() && h.im.is_nan()));
+    }
+
+    #[test]
+    fn test_unwrap_phase() {
+        let ramp = linear_freqs(0.0, -20.0, 101);
+        let wrapped = ramp.map(|p| Complex::from_polar(1.0, p).arg());
+        assert_relative_eq!(unwrap_phase(&wrapped), ramp, epsilon = 1e-12);
+        assert_relative_eq!(
+            unwrap_phase(&wrapped.add_scalar(2.0 * PI)),
+            ramp.add_scalar(2.0 * PI),
+            epsilon = 1e-12
+        );
+        assert_eq!(unwrap_phase(&DVector::zeros(0)).len(), 0);
+    }
+
+    // -d phase / dw by central differences, dropping both ends.
+    fn numerical_group_delay(freqs: &DVector<f64>, phase: &DVector<f64>) -> DVector<f64> {
+        DVector::from_fn(freqs.len() - 2, |i, _| {
+            -(phase[i + 2] - phase[i]) / (freqs[i + 2] - freqs[i])
+        })
+    }
+
+    #[test]
+    fn test_continuous_group_delay() {
+        // 1 / (s + a) has the delay a / (a^2 + w^2) and the phase delay atan(w / a) / w
+        let a = 2.0;
+        let tf = ContinuousTransferFunction::new(dvector![1.0], dvector![1.0, a]);
+        let freqs = linear_freqs(0.0, 10.0, 101);
+        assert_relative_eq!(
+            tf.group_delay(&freqs),
+            freqs.map(|w| a / (a * a + w * w)),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            tf.phase_delay(&freqs),
+            freqs.map(|w| if w == 0.0 {
+                1.0 / a
+            } else {
+                (w / a).atan() / w
+            }),
+            epsilon = 1e-12
+        );
+
+        let tf = ContinuousTransferFunction::butter(4, 10.0, BandType::HighPass);
+        let freqs = linear_freqs(1.0, 50.0, 10_001);
+        let expected = numerical_group_delay(&freqs, &phase(&tf.freq_response(&freqs)));
+        let group_delay = tf.group_delay(&freqs);
+        assert_relative_eq!(
+            group_delay.rows(1, expected.len()).into_owned(),
+            expected,
+            max_relative = 1e-4
+        );
+
+        // A high-pass filter has no transmission at DC and a Chebyshev type II filter has none
+        // at its zeros on the j w axis, where the delay is undefined
+        let at_dc = dvector![0.0];
+        assert_eq!(tf.group_delay(&at_dc)[0], 0.0);
+        assert_eq!(tf.phase_delay(&at_dc)[0], 0.0);
+        let tf = ContinuousTransferFunction::chebyshev2(4, 10.0, 40.0, BandType::LowPass);
+        let zero_freqs = roots(&tf.num).map(|z| z.im);
+        assert!(zero_freqs.iter().all(|w| w.abs() > 10.0));
+        assert_eq!(
+            tf.group_delay(&zero_freqs),
+            DVector::zeros(zero_freqs.len())
+        );
+    }
+
+    #[test]
+    fn test_bessel_delay_is_flat() {
+        // Over the passband the delay of a Bessel filter stays at its DC value, while that of a
+        // Butterworth filter of the same order peaks near the cutoff
+        let bessel = ContinuousTransferFunction::bessel(4, 1.0, BandType::LowPass);
+        let butter = ContinuousTransferFunction::butter(4, 1.0, BandType::LowPass);
+        let freqs = linear_freqs(0.0, 1.0, 101);
+
+        let deviation = |tf: &ContinuousTransferFunction| {
+            let group_delay = tf.group_delay(&freqs);
+            group_delay.map(|d| (d / group_delay[0] - 1.0).abs()).max()
+        };
+        assert!(deviation(&bessel) < 1e-3);
+        assert!(deviation(&butter) > 0.4);
+
+        // With flat group delay the phase is linear, so the phase delay is flat as well
+        let bessel_phase_delay = bessel.phase_delay(&freqs);
+        let bessel_group_delay = bessel.group_delay(&freqs);
+        assert_relative_eq!(bessel_phase_delay, bessel_group_delay, max_relative = 1e-3);
+
+        // The phase normalization puts the delay at 1 / cutoff
+        assert_relative_eq!(bessel_group_delay[0], 1.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_discrete_group_delay() {
+        let dt = 0.01;
+        let delay = DiscreteTransferFunction::new(dvector![0.0, 0.0, 0.0, 1.0], dvector![1.0], dt);
+        let freqs = discrete_freqs(dt, 51);
+        assert_relative_eq!(
+            delay.group_delay(&freqs),
+            DVector::from_element(51, 3.0 * dt),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            delay.phase_delay(&freqs),
+            DVector::from_element(51, 3.0 * dt),
+            epsilon = 1e-12
+        );
+
+        // A symmetric FIR filter delays every frequency by half its length
+        let fir = DiscreteFir::firwin(11, 10.0, Window::Hamming, BandType::LowPass, 1.0 / dt);
+        let tf = DiscreteTransferFunction::new(fir.taps.clone(), dvector![1.0], dt);
+        let freqs = linear_freqs(0.0, 2.0 * PI * 10.0, 51);
+        assert_relative_eq!(
+            tf.group_delay(&freqs),
+            DVector::from_element(51, 5.0 * dt),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            tf.phase_delay(&freqs),
+            DVector::from_element(51, 5.0 * dt),
+            epsilon = 1e-9
+        );
+
+        let tf = DiscreteTransferFunction::butter(4, 10.0, dt, BandType::LowPass);
+        let freqs = discrete_freqs(dt, 10_001);
+        let group_delay = tf.group_delay(&freqs);
+        let expected = numerical_group_delay(&freqs, &phase(&tf.freq_response(&freqs)));
+        assert_relative_eq!(
+            group_delay.rows(1, 5000).into_owned(),
+            expected.rows(0, 5000).into_owned(),
+            max_relative = 1e-4
+        );
+
+        // The delay is undefined where the response vanishes
+        let tf = DiscreteTransferFunction::new(dvector![1.0, 1.0], dvector![1.0], dt);
+        assert_eq!(tf.group_delay(&dvector![PI / dt])[0], 0.0);
+    }
+}
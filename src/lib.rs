@@ -1,9 +1,20 @@
 #![allow(clippy::toplevel_ref_arg)]
 
+pub mod convolution;
+pub mod dct;
 pub mod fft;
 pub mod filter_design;
+pub mod filter_order;
+pub mod fir;
+pub mod freq_response;
 pub mod lti;
 pub mod lti_conversion;
 pub mod math;
+pub mod noise;
+pub mod remez;
 pub mod signal_extension;
 pub mod signal_generator;
+pub mod spectral;
+pub mod stft;
+pub mod time_delay;
+pub mod window;
@@ -1,10 +1,16 @@
-use nalgebra::{stack, DMatrix, DVector};
+use nalgebra::{stack, Complex, DMatrix, DVector};
 
+use crate::convolution::{convolve, ConvolveMode};
 use crate::filter_design::{
-    design_bessel, design_butter, design_chebyshev1, design_chebyshev2, digital_to_analog_cutoff,
-    BandType,
+    design_bessel, design_bessel_zpk, design_butter, design_butter_zpk, design_chebyshev1,
+    design_chebyshev1_zpk, design_chebyshev2, design_chebyshev2_zpk, design_ellip,
+    design_ellip_zpk, digital_to_analog_band, digital_to_analog_cutoff, BandType,
 };
-use crate::math::expm;
+use crate::freq_response::{
+    continuous_freqs, discrete_freqs, phase, polyder, polyval, polyval_inverse,
+    state_space_response, vanishes, zpk_response,
+};
+use crate::math::{expm, roots};
 use crate::signal_extension::anti_symmetric_reflect_extension;
 
 const DEFAULT_ALPHA: f64 = 0.5;
@@ -28,6 +34,8 @@ impl ContinuousTransferFunction {
         Self { num, den, x }
     }
 
+    // Analog designs from filter_design, with frequencies in rad/s. For band filters the edges
+    // come from `filter_type` and `cutoff_freq` must be 0 or NaN.
     pub fn butter(order: usize, cutoff_freq: f64, filter_type: BandType) -> Self {
         design_butter(order, cutoff_freq, filter_type)
     }
@@ -54,6 +62,22 @@ impl ContinuousTransferFunction {
         design_chebyshev2(order, cutoff_freq, ripple_db, filter_type)
     }
 
+    pub fn ellip(
+        order: usize,
+        cutoff_freq: f64,
+        passband_ripple_db: f64,
+        stopband_atten_db: f64,
+        filter_type: BandType,
+    ) -> Self {
+        design_ellip(
+            order,
+            cutoff_freq,
+            passband_ripple_db,
+            stopband_atten_db,
+            filter_type,
+        )
+    }
+
     pub fn reset(&mut self) {
         LTI::reset(self)
     }
@@ -77,6 +101,49 @@ impl ContinuousTransferFunction {
         let discrete_state_space = state_space.to_discrete(dt, alpha);
         DiscreteTransferFunction::from(discrete_state_space)
     }
+
+    // Complex gain at each angular frequency in rad/s, with s = j w.
+    pub fn freq_response(&self, freqs: &DVector<f64>) -> DVector<Complex<f64>> {
+        freqs.map(|w| {
+            let s = Complex::new(0.0, w);
+            polyval(&self.num, s) / polyval(&self.den, s)
+        })
+    }
+
+    // Log-spaced angular frequencies covering the zeros and poles.
+    pub fn freq_grid(&self, n: usize) -> DVector<f64> {
+        continuous_freqs(&roots(&self.num), &roots(&self.den), n)
+    }
+
+    // -d arg H(j w) / dw in seconds, from Re(D'/D - N'/N) at s = j w. Where the response
+    // vanishes or has a pole the delay is undefined and 0 is returned.
+    pub fn group_delay(&self, freqs: &DVector<f64>) -> DVector<f64> {
+        let num_derivative = polyder(&self.num);
+        let den_derivative = polyder(&self.den);
+        freqs.map(|w| {
+            let s = Complex::new(0.0, w);
+            let num = polyval(&self.num, s);
+            let den = polyval(&self.den, s);
+            if vanishes(&self.num, num, w.abs()) || vanishes(&self.den, den, w.abs()) {
+                return 0.0;
+            }
+            (polyval(&den_derivative, s) / den - polyval(&num_derivative, s) / num).re
+        })
+    }
+
+    // -arg H(j w) / w in seconds. The phase is unwrapped along `freqs`, which should therefore
+    // start near DC and be dense enough to follow it. At DC the group delay is returned.
+    pub fn phase_delay(&self, freqs: &DVector<f64>) -> DVector<f64> {
+        let phase = phase(&self.freq_response(freqs));
+        let group_delay = self.group_delay(freqs);
+        DVector::from_fn(freqs.len(), |i, _| {
+            if freqs[i] == 0.0 {
+                group_delay[i]
+            } else {
+                -phase[i] / freqs[i]
+            }
+        })
+    }
 }
 
 impl LTI for ContinuousTransferFunction {
@@ -127,16 +194,23 @@ impl DiscreteTransferFunction {
         }
     }
 
+    // Digital designs through the bilinear transform, with frequencies in Hz prewarped to keep
+    // the edges in place. For band filters the edges come from `filter_type`, below the Nyquist
+    // frequency, and `cutoff_freq` must be 0 or NaN.
     pub fn butter(order: usize, cutoff_freq: f64, dt: f64, filter_type: BandType) -> Self {
         let sample_rate = 1.0 / dt;
         let normalized_cutoff_freq = digital_to_analog_cutoff(cutoff_freq, sample_rate);
-        design_butter(order, normalized_cutoff_freq, filter_type).to_discrete(dt, DEFAULT_ALPHA)
+        let analog_filter_type = digital_to_analog_band(filter_type, sample_rate);
+        design_butter(order, normalized_cutoff_freq, analog_filter_type)
+            .to_discrete(dt, DEFAULT_ALPHA)
     }
 
     pub fn bessel(order: usize, cutoff_freq: f64, dt: f64, filter_type: BandType) -> Self {
         let sample_rate = 1.0 / dt;
         let normalized_cutoff_freq = digital_to_analog_cutoff(cutoff_freq, sample_rate);
-        design_bessel(order, normalized_cutoff_freq, filter_type).to_discrete(dt, DEFAULT_ALPHA)
+        let analog_filter_type = digital_to_analog_band(filter_type, sample_rate);
+        design_bessel(order, normalized_cutoff_freq, analog_filter_type)
+            .to_discrete(dt, DEFAULT_ALPHA)
     }
 
     pub fn chebyshev1(
@@ -148,7 +222,8 @@ impl DiscreteTransferFunction {
     ) -> Self {
         let sample_rate = 1.0 / dt;
         let normalized_cutoff_freq = digital_to_analog_cutoff(cutoff_freq, sample_rate);
-        design_chebyshev1(order, normalized_cutoff_freq, ripple_db, filter_type)
+        let analog_filter_type = digital_to_analog_band(filter_type, sample_rate);
+        design_chebyshev1(order, normalized_cutoff_freq, ripple_db, analog_filter_type)
             .to_discrete(dt, DEFAULT_ALPHA)
     }
 
@@ -161,10 +236,32 @@ impl DiscreteTransferFunction {
     ) -> Self {
         let sample_rate = 1.0 / dt;
         let normalized_cutoff_freq = digital_to_analog_cutoff(cutoff_freq, sample_rate);
-        design_chebyshev2(order, normalized_cutoff_freq, ripple_db, filter_type)
+        let analog_filter_type = digital_to_analog_band(filter_type, sample_rate);
+        design_chebyshev2(order, normalized_cutoff_freq, ripple_db, analog_filter_type)
             .to_discrete(dt, DEFAULT_ALPHA)
     }
 
+    pub fn ellip(
+        order: usize,
+        cutoff_freq: f64,
+        passband_ripple_db: f64,
+        stopband_atten_db: f64,
+        dt: f64,
+        filter_type: BandType,
+    ) -> Self {
+        let sample_rate = 1.0 / dt;
+        let normalized_cutoff_freq = digital_to_analog_cutoff(cutoff_freq, sample_rate);
+        let analog_filter_type = digital_to_analog_band(filter_type, sample_rate);
+        design_ellip(
+            order,
+            normalized_cutoff_freq,
+            passband_ripple_db,
+            stopband_atten_db,
+            analog_filter_type,
+        )
+        .to_discrete(dt, DEFAULT_ALPHA)
+    }
+
     pub fn step(&mut self, input: f64) -> f64 {
         let mut output = 0.0;
 
@@ -194,6 +291,54 @@ impl DiscreteTransferFunction {
     pub fn filtfilt(&mut self, u: &DVector<f64>, t: &DVector<f64>) -> DVector<f64> {
         LTI::filtfilt(self, u, t)
     }
+
+    // Complex gain at each angular frequency in rad/s, with z = exp(j w dt).
+    pub fn freq_response(&self, freqs: &DVector<f64>) -> DVector<Complex<f64>> {
+        freqs.map(|w| {
+            let z = Complex::new(0.0, w * self.dt).exp();
+            polyval_inverse(&self.num, z) / polyval_inverse(&self.den, z)
+        })
+    }
+
+    // Linearly spaced angular frequencies from DC to Nyquist.
+    pub fn freq_grid(&self, n: usize) -> DVector<f64> {
+        discrete_freqs(self.dt, n)
+    }
+
+    // -d arg H / dw in seconds. The delay of B(z^-1) A(z) z^-(na - 1), a polynomial C, is the
+    // ratio of the ramp polynomial sum k c[k] z^-k to C, and the delay of H follows by
+    // subtracting na - 1 samples. Where the response vanishes the delay is undefined and 0 is
+    // returned.
+    pub fn group_delay(&self, freqs: &DVector<f64>) -> DVector<f64> {
+        let reversed_den = DVector::from_iterator(self.den.len(), self.den.iter().rev().copied());
+        let c = convolve(&self.num, &reversed_den, ConvolveMode::Full);
+        let ramp = DVector::from_fn(c.len(), |k, _| k as f64 * c[k]);
+        let singular = 10.0 * f64::EPSILON * c.amax();
+
+        freqs.map(|w| {
+            let z = Complex::new(0.0, w * self.dt).exp();
+            let den = polyval_inverse(&c, z);
+            if den.norm() < singular {
+                return 0.0;
+            }
+            let delay = (polyval_inverse(&ramp, z) / den).re - (self.den.len() - 1) as f64;
+            delay * self.dt
+        })
+    }
+
+    // -arg H / w in seconds. The phase is unwrapped along `freqs`, which should therefore start
+    // near DC and be dense enough to follow it. At DC the group delay is returned.
+    pub fn phase_delay(&self, freqs: &DVector<f64>) -> DVector<f64> {
+        let phase = phase(&self.freq_response(freqs));
+        let group_delay = self.group_delay(freqs);
+        DVector::from_fn(freqs.len(), |i, _| {
+            if freqs[i] == 0.0 {
+                group_delay[i]
+            } else {
+                -phase[i] / freqs[i]
+            }
+        })
+    }
 }
 
 impl LTI for DiscreteTransferFunction {
@@ -202,7 +347,7 @@ impl LTI for DiscreteTransferFunction {
         self.outputs = DVector::zeros(self.outputs.len());
     }
 
-    fn simulate(&mut self, inputs: DVector<f64>, t: DVector<f64>) -> DVector<f64> {
+    fn simulate(&mut self, inputs: DVector<f64>, _t: DVector<f64>) -> DVector<f64> {
         let mut output = DVector::zeros(inputs.len());
 
         for i in 0..inputs.len() {
@@ -340,6 +485,18 @@ impl ContinuousStateSpace {
 
         DiscreteStateSpace::new(ad, bd, cd, dd, dt)
     }
+
+    // Complex gain from the first input to the first output at each angular frequency in
+    // rad/s, with s = j w.
+    pub fn freq_response(&self, freqs: &DVector<f64>) -> DVector<Complex<f64>> {
+        freqs
+            .map(|w| state_space_response(&self.a, &self.b, &self.c, &self.d, Complex::new(0.0, w)))
+    }
+
+    // Log-spaced angular frequencies covering the poles.
+    pub fn freq_grid(&self, n: usize) -> DVector<f64> {
+        continuous_freqs(&DVector::zeros(0), &self.a.complex_eigenvalues(), n)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -370,6 +527,373 @@ impl DiscreteStateSpace {
 
         output[0]
     }
+
+    // Complex gain from the first input to the first output at each angular frequency in
+    // rad/s, with z = exp(j w dt).
+    pub fn freq_response(&self, freqs: &DVector<f64>) -> DVector<Complex<f64>> {
+        freqs.map(|w| {
+            let z = Complex::new(0.0, w * self.dt).exp();
+            state_space_response(&self.a, &self.b, &self.c, &self.d, z)
+        })
+    }
+
+    // Linearly spaced angular frequencies from DC to Nyquist.
+    pub fn freq_grid(&self, n: usize) -> DVector<f64> {
+        discrete_freqs(self.dt, n)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ContinuousZpk {
+    pub zeros: DVector<Complex<f64>>,
+    pub poles: DVector<Complex<f64>>,
+    pub gain: f64,
+}
+
+impl ContinuousZpk {
+    pub fn new(zeros: DVector<Complex<f64>>, poles: DVector<Complex<f64>>, gain: f64) -> Self {
+        Self { zeros, poles, gain }
+    }
+
+    // Analog designs in rad/s. For band filters `cutoff_freq` must be 0 or NaN.
+    pub fn butter(order: usize, cutoff_freq: f64, filter_type: BandType) -> Self {
+        design_butter_zpk(order, cutoff_freq, filter_type)
+    }
+
+    pub fn bessel(order: usize, cutoff_freq: f64, filter_type: BandType) -> Self {
+        design_bessel_zpk(order, cutoff_freq, filter_type)
+    }
+
+    pub fn chebyshev1(
+        order: usize,
+        cutoff_freq: f64,
+        ripple_db: f64,
+        filter_type: BandType,
+    ) -> Self {
+        design_chebyshev1_zpk(order, cutoff_freq, ripple_db, filter_type)
+    }
+
+    pub fn chebyshev2(
+        order: usize,
+        cutoff_freq: f64,
+        ripple_db: f64,
+        filter_type: BandType,
+    ) -> Self {
+        design_chebyshev2_zpk(order, cutoff_freq, ripple_db, filter_type)
+    }
+
+    pub fn ellip(
+        order: usize,
+        cutoff_freq: f64,
+        passband_ripple_db: f64,
+        stopband_atten_db: f64,
+        filter_type: BandType,
+    ) -> Self {
+        design_ellip_zpk(
+            order,
+            cutoff_freq,
+            passband_ripple_db,
+            stopband_atten_db,
+            filter_type,
+        )
+    }
+
+    // Maps every root through the same generalized bilinear transform as `to_discrete` on the
+    // state-space types. Zeros at infinity land on z = -(1 - alpha) / alpha.
+    pub fn to_discrete(&self, dt: f64, alpha: f64) -> DiscreteZpk {
+        let transform = |r: &Complex<f64>| (1.0 + (1.0 - alpha) * dt * r) / (1.0 - alpha * dt * r);
+        let degree = self.poles.len() - self.zeros.len();
+
+        let mut gain = self.gain
+            * (self.zeros.map(|z| 1.0 - alpha * dt * z).product()
+                / self.poles.map(|p| 1.0 - alpha * dt * p).product())
+            .re;
+        let zeros = if alpha > 0.0 {
+            gain *= (alpha * dt).powi(degree as i32);
+            stack![
+                self.zeros.map(|z| transform(&z));
+                DVector::from_element(degree, Complex::new(-(1.0 - alpha) / alpha, 0.0))
+            ]
+        } else {
+            gain *= dt.powi(degree as i32);
+            self.zeros.map(|z| transform(&z))
+        };
+
+        DiscreteZpk::new(zeros, self.poles.map(|p| transform(&p)), gain, dt)
+    }
+
+    // Complex gain at each angular frequency in rad/s, with s = j w.
+    pub fn freq_response(&self, freqs: &DVector<f64>) -> DVector<Complex<f64>> {
+        freqs.map(|w| zpk_response(&self.zeros, &self.poles, self.gain, Complex::new(0.0, w)))
+    }
+
+    // Log-spaced angular frequencies covering the zeros and poles.
+    pub fn freq_grid(&self, n: usize) -> DVector<f64> {
+        continuous_freqs(&self.zeros, &self.poles, n)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DiscreteZpk {
+    pub zeros: DVector<Complex<f64>>,
+    pub poles: DVector<Complex<f64>>,
+    pub gain: f64,
+    pub dt: f64,
+}
+
+impl DiscreteZpk {
+    pub fn new(
+        zeros: DVector<Complex<f64>>,
+        poles: DVector<Complex<f64>>,
+        gain: f64,
+        dt: f64,
+    ) -> Self {
+        Self {
+            zeros,
+            poles,
+            gain,
+            dt,
+        }
+    }
+
+    // Digital designs in Hz, as for DiscreteTransferFunction. For band filters `cutoff_freq`
+    // must be 0 or NaN.
+    pub fn butter(order: usize, cutoff_freq: f64, dt: f64, filter_type: BandType) -> Self {
+        let sample_rate = 1.0 / dt;
+        let normalized_cutoff_freq = digital_to_analog_cutoff(cutoff_freq, sample_rate);
+        let analog_filter_type = digital_to_analog_band(filter_type, sample_rate);
+        design_butter_zpk(order, normalized_cutoff_freq, analog_filter_type)
+            .to_discrete(dt, DEFAULT_ALPHA)
+    }
+
+    pub fn bessel(order: usize, cutoff_freq: f64, dt: f64, filter_type: BandType) -> Self {
+        let sample_rate = 1.0 / dt;
+        let normalized_cutoff_freq = digital_to_analog_cutoff(cutoff_freq, sample_rate);
+        let analog_filter_type = digital_to_analog_band(filter_type, sample_rate);
+        design_bessel_zpk(order, normalized_cutoff_freq, analog_filter_type)
+            .to_discrete(dt, DEFAULT_ALPHA)
+    }
+
+    pub fn chebyshev1(
+        order: usize,
+        cutoff_freq: f64,
+        ripple_db: f64,
+        dt: f64,
+        filter_type: BandType,
+    ) -> Self {
+        let sample_rate = 1.0 / dt;
+        let normalized_cutoff_freq = digital_to_analog_cutoff(cutoff_freq, sample_rate);
+        let analog_filter_type = digital_to_analog_band(filter_type, sample_rate);
+        design_chebyshev1_zpk(order, normalized_cutoff_freq, ripple_db, analog_filter_type)
+            .to_discrete(dt, DEFAULT_ALPHA)
+    }
+
+    pub fn chebyshev2(
+        order: usize,
+        cutoff_freq: f64,
+        ripple_db: f64,
+        dt: f64,
+        filter_type: BandType,
+    ) -> Self {
+        let sample_rate = 1.0 / dt;
+        let normalized_cutoff_freq = digital_to_analog_cutoff(cutoff_freq, sample_rate);
+        let analog_filter_type = digital_to_analog_band(filter_type, sample_rate);
+        design_chebyshev2_zpk(order, normalized_cutoff_freq, ripple_db, analog_filter_type)
+            .to_discrete(dt, DEFAULT_ALPHA)
+    }
+
+    pub fn ellip(
+        order: usize,
+        cutoff_freq: f64,
+        passband_ripple_db: f64,
+        stopband_atten_db: f64,
+        dt: f64,
+        filter_type: BandType,
+    ) -> Self {
+        let sample_rate = 1.0 / dt;
+        let normalized_cutoff_freq = digital_to_analog_cutoff(cutoff_freq, sample_rate);
+        let analog_filter_type = digital_to_analog_band(filter_type, sample_rate);
+        design_ellip_zpk(
+            order,
+            normalized_cutoff_freq,
+            passband_ripple_db,
+            stopband_atten_db,
+            analog_filter_type,
+        )
+        .to_discrete(dt, DEFAULT_ALPHA)
+    }
+
+    // Complex gain at each angular frequency in rad/s, with z = exp(j w dt).
+    pub fn freq_response(&self, freqs: &DVector<f64>) -> DVector<Complex<f64>> {
+        freqs.map(|w| {
+            let z = Complex::new(0.0, w * self.dt).exp();
+            zpk_response(&self.zeros, &self.poles, self.gain, z)
+        })
+    }
+
+    // Linearly spaced angular frequencies from DC to Nyquist.
+    pub fn freq_grid(&self, n: usize) -> DVector<f64> {
+        discrete_freqs(self.dt, n)
+    }
+}
+
+// A cascade of biquads, one row [b0, b1, b2, a0, a1, a2] per section, run in transposed
+// direct form II.
+#[derive(Clone, Debug)]
+pub struct DiscreteSos {
+    pub sections: DMatrix<f64>,
+    states: DMatrix<f64>,
+    pub dt: f64,
+}
+
+impl DiscreteSos {
+    pub fn new(sections: DMatrix<f64>, dt: f64) -> Self {
+        assert_eq!(
+            sections.ncols(),
+            6,
+            "Each section must have 6 coefficients."
+        );
+
+        let states = DMatrix::zeros(sections.nrows(), 2);
+        Self {
+            sections,
+            states,
+            dt,
+        }
+    }
+
+    // The DiscreteZpk designs split into sections. For band filters `cutoff_freq` must be 0 or
+    // NaN.
+    pub fn butter(order: usize, cutoff_freq: f64, dt: f64, filter_type: BandType) -> Self {
+        DiscreteZpk::butter(order, cutoff_freq, dt, filter_type).into()
+    }
+
+    pub fn bessel(order: usize, cutoff_freq: f64, dt: f64, filter_type: BandType) -> Self {
+        DiscreteZpk::bessel(order, cutoff_freq, dt, filter_type).into()
+    }
+
+    pub fn chebyshev1(
+        order: usize,
+        cutoff_freq: f64,
+        ripple_db: f64,
+        dt: f64,
+        filter_type: BandType,
+    ) -> Self {
+        DiscreteZpk::chebyshev1(order, cutoff_freq, ripple_db, dt, filter_type).into()
+    }
+
+    pub fn chebyshev2(
+        order: usize,
+        cutoff_freq: f64,
+        ripple_db: f64,
+        dt: f64,
+        filter_type: BandType,
+    ) -> Self {
+        DiscreteZpk::chebyshev2(order, cutoff_freq, ripple_db, dt, filter_type).into()
+    }
+
+    pub fn ellip(
+        order: usize,
+        cutoff_freq: f64,
+        passband_ripple_db: f64,
+        stopband_atten_db: f64,
+        dt: f64,
+        filter_type: BandType,
+    ) -> Self {
+        DiscreteZpk::ellip(
+            order,
+            cutoff_freq,
+            passband_ripple_db,
+            stopband_atten_db,
+            dt,
+            filter_type,
+        )
+        .into()
+    }
+
+    pub fn step(&mut self, input: f64) -> f64 {
+        let mut x = input;
+
+        for i in 0..self.sections.nrows() {
+            let a0 = self.sections[(i, 3)];
+            let [b0, b1, b2] = [0, 1, 2].map(|j| self.sections[(i, j)] / a0);
+            let [a1, a2] = [4, 5].map(|j| self.sections[(i, j)] / a0);
+
+            let y = b0 * x + self.states[(i, 0)];
+            self.states[(i, 0)] = b1 * x - a1 * y + self.states[(i, 1)];
+            self.states[(i, 1)] = b2 * x - a2 * y;
+            x = y;
+        }
+
+        x
+    }
+
+    pub fn reset(&mut self) {
+        LTI::reset(self)
+    }
+
+    pub fn simulate(&mut self, inputs: DVector<f64>, t: DVector<f64>) -> DVector<f64> {
+        LTI::simulate(self, inputs, t)
+    }
+
+    pub fn filtfilt(&mut self, u: &DVector<f64>, t: &DVector<f64>) -> DVector<f64> {
+        LTI::filtfilt(self, u, t)
+    }
+
+    // Complex gain at each angular frequency in rad/s, with z = exp(j w dt).
+    pub fn freq_response(&self, freqs: &DVector<f64>) -> DVector<Complex<f64>> {
+        freqs.map(|w| {
+            let z = Complex::new(0.0, w * self.dt).exp();
+            self.sections
+                .row_iter()
+                .map(|section| {
+                    let section = section.transpose();
+                    polyval_inverse(&section.rows(0, 3).into_owned(), z)
+                        / polyval_inverse(&section.rows(3, 3).into_owned(), z)
+                })
+                .product()
+        })
+    }
+
+    // Linearly spaced angular frequencies from DC to Nyquist.
+    pub fn freq_grid(&self, n: usize) -> DVector<f64> {
+        discrete_freqs(self.dt, n)
+    }
+}
+
+impl LTI for DiscreteSos {
+    fn reset(&mut self) {
+        self.states = DMatrix::zeros(self.sections.nrows(), 2);
+    }
+
+    fn simulate(&mut self, inputs: DVector<f64>, _t: DVector<f64>) -> DVector<f64> {
+        inputs.map(|input| self.step(input))
+    }
+
+    fn filtfilt(&mut self, u: &DVector<f64>, t: &DVector<f64>) -> DVector<f64> {
+        // padding
+        let u_extended = anti_symmetric_reflect_extension(u.clone());
+        let dt = t[1] - t[0];
+        let t_extended = stack![t; DVector::from_iterator(t.len() * 2, (1..=t.len() * 2).map(|i| t[0] + i as f64 * dt))];
+
+        // forward filtering
+        self.reset();
+        let y_extended = self.simulate(u_extended, t_extended.clone());
+
+        // backward filtering
+        self.reset();
+        let y_extended = DVector::from_iterator(
+            y_extended.len(),
+            y_extended.as_slice().iter().rev().copied(),
+        );
+        let y_extended = self.simulate(y_extended, t_extended);
+        let y_extended = DVector::from_iterator(
+            y_extended.len(),
+            y_extended.as_slice().iter().rev().copied(),
+        );
+
+        y_extended.rows(u.nrows(), u.nrows()).into_owned()
+    }
 }
 
 #[cfg(test)]
@@ -487,4 +1011,51 @@ mod tests {
 
         assert_relative_eq!(y, low_frequency_sin_wave, epsilon = 0.03);
     }
+
+    #[test]
+    fn test_step_discrete_sos() {
+        let sections = dmatrix![1.3, 0.0, 0.0, 2.0, 1.5, 0.0];
+        let mut sos = DiscreteSos::new(sections, 0.1);
+
+        let inputs = [0.2, 0.4, 0.6, 0.8, 1.0];
+        let outputs = inputs
+            .iter()
+            .map(|input| sos.step(*input))
+            .collect::<Vec<_>>();
+        let expected_outputs = [0.13, 0.1625, 0.268125, 0.31890625, 0.4108203125];
+
+        for (output, expected_output) in outputs.iter().zip(expected_outputs.iter()) {
+            assert_relative_eq!(output, expected_output);
+        }
+    }
+
+    #[test]
+    fn test_high_order_discrete_sos_is_stable() {
+        let sample_rate = 10000.0;
+        let dt = 1.0 / sample_rate;
+        let mut sos = DiscreteSos::chebyshev1(10, 10.0, 1.0, dt, BandType::LowPass);
+        assert_eq!(sos.sections.nrows(), 5);
+
+        let n = 100000;
+        let t = DVector::from_fn(n, |i, _| i as f64 * dt);
+        let response = sos.simulate(DVector::from_element(n, 1.0), t);
+
+        assert!(response.iter().all(|y| y.is_finite() && y.abs() < 2.0));
+        assert_relative_eq!(response[n - 1], 10.0_f64.powf(-1.0 / 20.0), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_filtfilt_discrete_sos() {
+        let sample_rate = 8000;
+        let dt = 1.0 / sample_rate as f64;
+        let t = DVector::from_fn(sample_rate + 1, |i, _| i as f64 * dt);
+        let low_frequency_sin_wave = (2.0 * PI * 10.0 * t.clone()).map(|e| e.sin());
+        let high_frequency_sin_wave = (2.0 * PI * 500.0 * t.clone()).map(|e| e.sin());
+        let x = low_frequency_sin_wave.clone() + high_frequency_sin_wave;
+
+        let mut sos = DiscreteSos::butter(8, 50.0, dt, BandType::LowPass);
+        let y = sos.filtfilt(&x, &t);
+
+        assert_relative_eq!(y, low_frequency_sin_wave, epsilon = 1e-2);
+    }
 }
@@ -1,9 +1,12 @@
-use nalgebra::{stack, DMatrix, DVector};
+use nalgebra::{dvector, stack, Complex, DMatrix, DVector};
 
+use crate::convolution::{convolve_complex, ConvolveMode};
+use crate::fir::DiscreteFir;
 use crate::lti::{
-    ContinuousStateSpace, ContinuousTransferFunction, DiscreteStateSpace, DiscreteTransferFunction,
+    ContinuousStateSpace, ContinuousTransferFunction, ContinuousZpk, DiscreteSos,
+    DiscreteStateSpace, DiscreteTransferFunction, DiscreteZpk,
 };
-use crate::math::characteristic_polynomial;
+use crate::math::{characteristic_polynomial, polynomial, roots};
 
 impl From<ContinuousStateSpace> for ContinuousTransferFunction {
     fn from(state_space: ContinuousStateSpace) -> Self {
@@ -96,11 +99,270 @@ impl From<DiscreteTransferFunction> for DiscreteStateSpace {
     }
 }
 
+impl From<ContinuousZpk> for ContinuousTransferFunction {
+    fn from(zpk: ContinuousZpk) -> Self {
+        let num = polynomial(zpk.zeros).map(|e| e.re * zpk.gain);
+        let den = polynomial(zpk.poles).map(|e| e.re);
+
+        Self::new(num, den)
+    }
+}
+
+impl From<ContinuousTransferFunction> for ContinuousZpk {
+    fn from(tf: ContinuousTransferFunction) -> Self {
+        let leading_num = tf.num.iter().find(|e| **e != 0.0).copied().unwrap_or(0.0);
+        let gain = leading_num / tf.den[0];
+
+        Self::new(roots(&tf.num), roots(&tf.den), gain)
+    }
+}
+
+impl From<DiscreteZpk> for DiscreteTransferFunction {
+    fn from(zpk: DiscreteZpk) -> Self {
+        assert!(
+            zpk.poles.len() >= zpk.zeros.len(),
+            "The number of poles must be greater than or equal to the number of zeros."
+        );
+
+        // Pad the numerator so that both polynomials are in powers of z^-1
+        let delay = zpk.poles.len() - zpk.zeros.len();
+        let num = stack![DVector::zeros(delay); polynomial(zpk.zeros).map(|e| e.re * zpk.gain)];
+        let den = polynomial(zpk.poles).map(|e| e.re);
+
+        Self::new(num, den, zpk.dt)
+    }
+}
+
+impl From<DiscreteTransferFunction> for DiscreteZpk {
+    fn from(tf: DiscreteTransferFunction) -> Self {
+        // Coefficients are in powers of z^-1, so pad both to the same length before
+        // reading them as polynomials in z.
+        let n = tf.num.len().max(tf.den.len());
+        let num = stack![tf.num.clone(); DVector::zeros(n - tf.num.len())];
+        let den = stack![tf.den.clone(); DVector::zeros(n - tf.den.len())];
+
+        let leading_num = num.iter().find(|e| **e != 0.0).copied().unwrap_or(0.0);
+        let gain = leading_num / den[0];
+
+        Self::new(roots(&num), roots(&den), gain, tf.dt)
+    }
+}
+
+impl From<ContinuousZpk> for ContinuousStateSpace {
+    fn from(zpk: ContinuousZpk) -> Self {
+        ContinuousStateSpace::from(ContinuousTransferFunction::from(zpk))
+    }
+}
+
+impl From<ContinuousStateSpace> for ContinuousZpk {
+    fn from(state_space: ContinuousStateSpace) -> Self {
+        ContinuousZpk::from(ContinuousTransferFunction::from(state_space))
+    }
+}
+
+impl From<DiscreteZpk> for DiscreteStateSpace {
+    fn from(zpk: DiscreteZpk) -> Self {
+        DiscreteStateSpace::from(DiscreteTransferFunction::from(zpk))
+    }
+}
+
+impl From<DiscreteStateSpace> for DiscreteZpk {
+    fn from(state_space: DiscreteStateSpace) -> Self {
+        DiscreteZpk::from(DiscreteTransferFunction::from(state_space))
+    }
+}
+
+impl From<DiscreteZpk> for DiscreteSos {
+    // Pairs each pole with its nearest zero, starting from the pole closest to the unit circle,
+    // and puts those sections last so that the earlier sections attenuate the signal first.
+    fn from(zpk: DiscreteZpk) -> Self {
+        if zpk.zeros.is_empty() && zpk.poles.is_empty() {
+            return DiscreteSos::new(
+                DMatrix::from_row_slice(1, 6, &[zpk.gain, 0.0, 0.0, 1.0, 0.0, 0.0]),
+                zpk.dt,
+            );
+        }
+
+        let mut zeros = zpk.zeros.iter().copied().collect::<Vec<_>>();
+        let mut poles = zpk.poles.iter().copied().collect::<Vec<_>>();
+        let n = zeros.len().max(poles.len());
+        zeros.resize(n, Complex::new(0.0, 0.0));
+        poles.resize(n, Complex::new(0.0, 0.0));
+        let n_sections = n.div_ceil(2);
+        if n % 2 == 1 {
+            zeros.push(Complex::new(0.0, 0.0));
+            poles.push(Complex::new(0.0, 0.0));
+        }
+
+        let mut zeros = upper_half_roots(&zeros);
+        let mut poles = upper_half_roots(&poles);
+
+        let mut sections = vec![];
+        for _ in 0..n_sections {
+            let p1 = poles.remove(argmin(&poles, |p| (1.0 - p.norm()).abs()));
+            let n_real_poles = poles.iter().filter(|p| p.im == 0.0).count();
+            let n_real_zeros = zeros.iter().filter(|z| z.im == 0.0).count();
+
+            let section = if p1.im == 0.0 && n_real_poles == 0 {
+                // The last real pole pairs with the nearest real zero
+                let z1 = zeros.remove(nearest(&zeros, p1, |z| z.im == 0.0));
+                ([z1, Complex::new(0.0, 0.0)], [p1, Complex::new(0.0, 0.0)])
+            } else if poles.len() + 1 == zeros.len()
+                && p1.im != 0.0
+                && n_real_poles == 1
+                && n_real_zeros == 1
+            {
+                // A complex pole must take a complex zero so the last real pair stays together
+                let z1 = zeros.remove(nearest(&zeros, p1, |z| z.im != 0.0));
+                ([z1, z1.conj()], [p1, p1.conj()])
+            } else {
+                let p2 = if p1.im == 0.0 {
+                    poles.remove(argmin_where(
+                        &poles,
+                        |p| (p.norm() - 1.0).abs(),
+                        |p| p.im == 0.0,
+                    ))
+                } else {
+                    p1.conj()
+                };
+
+                let z1 = zeros.remove(nearest(&zeros, p1, |_| true));
+                if z1.im != 0.0 {
+                    ([z1, z1.conj()], [p1, p2])
+                } else {
+                    let z2 = zeros.remove(nearest(&zeros, p1, |z| z.im == 0.0));
+                    ([z1, z2], [p1, p2])
+                }
+            };
+            sections.push(section);
+        }
+
+        let mut coefficients = DMatrix::zeros(n_sections, 6);
+        for (i, (section_zeros, section_poles)) in sections.iter().rev().enumerate() {
+            let gain = if i == 0 { zpk.gain } else { 1.0 };
+            let num = polynomial(DVector::from_row_slice(section_zeros)).map(|e| e.re * gain);
+            let den = polynomial(DVector::from_row_slice(section_poles)).map(|e| e.re);
+            for j in 0..3 {
+                coefficients[(i, j)] = num[j];
+                coefficients[(i, j + 3)] = den[j];
+            }
+        }
+
+        DiscreteSos::new(coefficients, zpk.dt)
+    }
+}
+
+// Keeps one root of every complex-conjugate pair, snapping nearly real roots onto the real axis.
+fn upper_half_roots(roots: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let tolerance = |r: &Complex<f64>| 100.0 * f64::EPSILON * r.norm().max(1.0);
+
+    let mut result = vec![];
+    for r in roots {
+        if r.im.abs() <= tolerance(r) {
+            result.push(Complex::new(r.re, 0.0));
+        } else if r.im > 0.0 {
+            result.push(*r);
+        }
+    }
+    result
+}
+
+fn argmin(roots: &[Complex<f64>], key: impl Fn(&Complex<f64>) -> f64) -> usize {
+    argmin_where(roots, key, |_| true)
+}
+
+fn argmin_where(
+    roots: &[Complex<f64>],
+    key: impl Fn(&Complex<f64>) -> f64,
+    filter: impl Fn(&Complex<f64>) -> bool,
+) -> usize {
+    (0..roots.len())
+        .filter(|i| filter(&roots[*i]))
+        .min_by(|a, b| key(&roots[*a]).total_cmp(&key(&roots[*b])))
+        .unwrap()
+}
+
+fn nearest(
+    roots: &[Complex<f64>],
+    target: Complex<f64>,
+    filter: impl Fn(&Complex<f64>) -> bool,
+) -> usize {
+    argmin_where(roots, |r| (r - target).norm(), filter)
+}
+
+impl From<DiscreteSos> for DiscreteZpk {
+    fn from(sos: DiscreteSos) -> Self {
+        let mut zeros = vec![];
+        let mut poles = vec![];
+        let mut gain = 1.0;
+
+        for section in sos.sections.row_iter() {
+            let num = DVector::from_iterator(3, section.columns(0, 3).iter().copied());
+            let den = DVector::from_iterator(3, section.columns(3, 3).iter().copied());
+
+            zeros.extend(roots(&num).iter().copied());
+            poles.extend(roots(&den).iter().copied());
+            gain *= num.iter().find(|e| **e != 0.0).copied().unwrap_or(0.0) / den[0];
+        }
+
+        // Roots at the origin only come from padding sections up to second order
+        while let Some(i) = zeros.iter().position(|z| z.norm() == 0.0) {
+            let Some(j) = poles.iter().position(|p| p.norm() == 0.0) else {
+                break;
+            };
+            zeros.remove(i);
+            poles.remove(j);
+        }
+
+        DiscreteZpk::new(
+            DVector::from_vec(zeros),
+            DVector::from_vec(poles),
+            gain,
+            sos.dt,
+        )
+    }
+}
+
+impl From<DiscreteTransferFunction> for DiscreteSos {
+    fn from(tf: DiscreteTransferFunction) -> Self {
+        DiscreteSos::from(DiscreteZpk::from(tf))
+    }
+}
+
+impl From<DiscreteSos> for DiscreteTransferFunction {
+    fn from(sos: DiscreteSos) -> Self {
+        let mut num = dvector![Complex::new(1.0, 0.0)];
+        let mut den = dvector![Complex::new(1.0, 0.0)];
+
+        for section in sos.sections.row_iter() {
+            let to_complex = |start: usize| {
+                DVector::from_iterator(
+                    3,
+                    section
+                        .columns(start, 3)
+                        .iter()
+                        .map(|e| Complex::new(*e, 0.0)),
+                )
+            };
+            num = convolve_complex(&num, &to_complex(0), ConvolveMode::Full);
+            den = convolve_complex(&den, &to_complex(3), ConvolveMode::Full);
+        }
+
+        Self::new(num.map(|e| e.re), den.map(|e| e.re), sos.dt)
+    }
+}
+
+impl From<DiscreteFir> for DiscreteTransferFunction {
+    fn from(fir: DiscreteFir) -> Self {
+        Self::new(fir.taps, dvector![1.0], fir.dt)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use approx::assert_relative_eq;
-    use nalgebra::{dmatrix, dvector};
+    use nalgebra::{dmatrix, dvector, Complex};
 
     #[test]
     fn test_continuous_transfer_function_to_continuous_state_space() {
@@ -198,4 +460,156 @@ mod tests {
             epsilon = 1e-15
         );
     }
+
+    fn sorted(roots: &DVector<Complex<f64>>) -> Vec<Complex<f64>> {
+        let mut roots = roots.iter().copied().collect::<Vec<_>>();
+        roots.sort_by(|a, b| (a.re, a.im).partial_cmp(&(b.re, b.im)).unwrap());
+        roots
+    }
+
+    #[test]
+    fn test_continuous_zpk_to_continuous_transfer_function() {
+        let zpk = ContinuousZpk::new(
+            dvector![Complex::new(-3.0, 0.0)],
+            dvector![Complex::new(-1.0, 1.0), Complex::new(-1.0, -1.0)],
+            2.0,
+        );
+        let tf = ContinuousTransferFunction::from(zpk);
+
+        assert_relative_eq!(tf.num, dvector![2.0, 6.0]);
+        assert_relative_eq!(tf.den, dvector![1.0, 2.0, 2.0]);
+
+        let zpk = ContinuousZpk::from(tf);
+        assert_relative_eq!(zpk.gain, 2.0);
+        assert_relative_eq!(zpk.zeros[0].re, -3.0, epsilon = 1e-15);
+        let poles = sorted(&zpk.poles);
+        assert_relative_eq!(poles[0].re, -1.0, epsilon = 1e-15);
+        assert_relative_eq!(poles[0].im, -1.0, epsilon = 1e-15);
+        assert_relative_eq!(poles[1].im, 1.0, epsilon = 1e-15);
+    }
+
+    #[test]
+    fn test_discrete_zpk_to_discrete_transfer_function() {
+        let zpk = DiscreteZpk::new(
+            DVector::zeros(0),
+            dvector![Complex::new(0.5, 0.0)],
+            1.0,
+            0.1,
+        );
+        let mut tf = DiscreteTransferFunction::from(zpk);
+
+        assert_relative_eq!(tf.num, dvector![0.0, 1.0]);
+        assert_relative_eq!(tf.den, dvector![1.0, -0.5]);
+        assert_relative_eq!(tf.dt, 0.1);
+
+        // y[n] = 0.5 y[n - 1] + x[n - 1]
+        let outputs = [1.0, 0.0, 0.0].map(|input| tf.step(input));
+        assert_relative_eq!(outputs.as_slice(), [0.0, 1.0, 0.5].as_slice());
+
+        let zpk = DiscreteZpk::from(tf);
+        assert_eq!(zpk.zeros.len(), 0);
+        assert_relative_eq!(zpk.poles[0].re, 0.5);
+        assert_relative_eq!(zpk.gain, 1.0);
+    }
+
+    #[test]
+    fn test_zpk_state_space_round_trip() {
+        let zpk = ContinuousZpk::new(
+            dvector![Complex::new(-2.0, 0.0)],
+            dvector![Complex::new(-1.0, 0.0), Complex::new(-4.0, 0.0)],
+            3.0,
+        );
+        let zpk = ContinuousZpk::from(ContinuousStateSpace::from(zpk));
+        assert_relative_eq!(zpk.gain, 3.0, epsilon = 1e-12);
+        assert_relative_eq!(zpk.zeros[0].re, -2.0, epsilon = 1e-12);
+        let poles = sorted(&zpk.poles);
+        assert_relative_eq!(poles[0].re, -4.0, epsilon = 1e-12);
+        assert_relative_eq!(poles[1].re, -1.0, epsilon = 1e-12);
+
+        let zpk = DiscreteZpk::new(
+            dvector![Complex::new(-1.0, 0.0)],
+            dvector![Complex::new(0.2, 0.3), Complex::new(0.2, -0.3)],
+            0.5,
+            0.01,
+        );
+        let zpk = DiscreteZpk::from(DiscreteStateSpace::from(zpk));
+        assert_relative_eq!(zpk.gain, 0.5, epsilon = 1e-12);
+        assert_relative_eq!(zpk.zeros[0].re, -1.0, epsilon = 1e-12);
+        let poles = sorted(&zpk.poles);
+        assert_relative_eq!(poles[0].im, -0.3, epsilon = 1e-12);
+        assert_relative_eq!(poles[1].im, 0.3, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_discrete_transfer_function_to_discrete_sos() {
+        let tf = DiscreteTransferFunction::new(
+            dvector![1.0, 3.0, 3.0, 1.0],
+            dvector![1.0, -0.5, 0.25, -0.125],
+            0.1,
+        );
+        let sos = DiscreteSos::from(tf.clone());
+        assert_eq!(sos.sections.nrows(), 2);
+
+        let round_trip = DiscreteTransferFunction::from(sos);
+        assert_relative_eq!(
+            round_trip.num,
+            stack![tf.num; dvector![0.0]],
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            round_trip.den,
+            stack![tf.den; dvector![0.0]],
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_discrete_zpk_to_discrete_sos_pairing() {
+        let zpk = DiscreteZpk::new(
+            dvector![
+                Complex::new(-1.0, 0.0),
+                Complex::new(0.0, 1.0),
+                Complex::new(0.0, -1.0),
+            ],
+            dvector![
+                Complex::new(0.5, 0.0),
+                Complex::new(0.0, 0.95),
+                Complex::new(0.0, -0.95),
+            ],
+            2.0,
+            1.0,
+        );
+        let sos = DiscreteSos::from(zpk);
+
+        // The first section carries the gain and the real pole with its real zero
+        assert_relative_eq!(
+            sos.sections.row(0).transpose(),
+            dvector![2.0, 2.0, 0.0, 1.0, -0.5, 0.0]
+        );
+        // The pole pair closest to the unit circle comes last with the zeros on it
+        assert_relative_eq!(
+            sos.sections.row(1).transpose(),
+            dvector![1.0, 0.0, 1.0, 1.0, 0.0, 0.9025],
+            epsilon = 1e-15
+        );
+
+        let zpk = DiscreteZpk::from(sos);
+        assert_eq!(zpk.zeros.len(), 3);
+        assert_eq!(zpk.poles.len(), 3);
+        assert_relative_eq!(zpk.gain, 2.0);
+    }
+
+    #[test]
+    fn test_discrete_fir_to_discrete_transfer_function() {
+        let mut fir = DiscreteFir::new(dvector![0.25, 0.5, 0.25], 0.01);
+        let mut tf = DiscreteTransferFunction::from(fir.clone());
+
+        assert_relative_eq!(tf.num, dvector![0.25, 0.5, 0.25]);
+        assert_relative_eq!(tf.den, dvector![1.0]);
+        assert_relative_eq!(tf.dt, 0.01);
+
+        for input in [1.0, -2.0, 0.5, 3.0] {
+            assert_relative_eq!(fir.step(input), tf.step(input));
+        }
+    }
 }
@@ -1,14 +1,46 @@
+use std::f64::consts::PI;
+
 use nalgebra::{stack, Complex, DMatrix, DVector};
 
+use crate::convolution::{convolve_complex, ConvolveMode};
+
 pub fn polynomial(vec: DVector<Complex<f64>>) -> DVector<Complex<f64>> {
     let mut a = DVector::from_vec(vec![Complex::new(1.0, 0.0)]);
     for x in vec.iter() {
-        a = convolve(&a, &DVector::from_vec(vec![Complex::new(1.0, 0.0), -x]));
+        a = convolve_complex(
+            &a,
+            &DVector::from_vec(vec![Complex::new(1.0, 0.0), -x]),
+            ConvolveMode::Full,
+        );
     }
 
     a
 }
 
+// Roots of a polynomial with coefficients in descending powers, via the companion matrix.
+pub fn roots(coeffs: &DVector<f64>) -> DVector<Complex<f64>> {
+    let Some(first) = coeffs.iter().position(|c| *c != 0.0) else {
+        return DVector::zeros(0);
+    };
+    let last = coeffs.iter().rposition(|c| *c != 0.0).unwrap();
+    let n = last - first;
+    let trailing_zeros = coeffs.len() - 1 - last;
+
+    if n == 0 {
+        return DVector::zeros(trailing_zeros);
+    }
+
+    let mut companion = DMatrix::<f64>::zeros(n, n);
+    for i in 0..n {
+        companion[(0, i)] = -coeffs[first + i + 1] / coeffs[first];
+    }
+    for i in 1..n {
+        companion[(i, i - 1)] = 1.0;
+    }
+
+    stack![companion.complex_eigenvalues(); DVector::zeros(trailing_zeros)]
+}
+
 pub fn characteristic_polynomial(matrix: &DMatrix<f64>) -> Option<DVector<f64>> {
     assert_eq!(matrix.nrows(), matrix.ncols(), "Matrix must be square.");
 
@@ -16,9 +48,10 @@ pub fn characteristic_polynomial(matrix: &DMatrix<f64>) -> Option<DVector<f64>>
     let mut complex_coeffs = DVector::from_vec(vec![Complex::new(1.0, 0.0)]);
 
     for complex_eigenvalue in complex_eigenvalues.iter() {
-        complex_coeffs = convolve(
+        complex_coeffs = convolve_complex(
             &complex_coeffs,
             &DVector::from_vec(vec![Complex::new(1.0, 0.0), -complex_eigenvalue]),
+            ConvolveMode::Full,
         );
     }
     let coeffs = DVector::from_vec(complex_coeffs.iter().map(|e| e.re).collect::<Vec<_>>());
@@ -41,24 +74,6 @@ pub fn expm(matrix: &DMatrix<f64>) -> DMatrix<f64> {
     result
 }
 
-pub fn convolve(a: &DVector<Complex<f64>>, b: &DVector<Complex<f64>>) -> DVector<Complex<f64>> {
-    let n = a.len();
-    let m = b.len();
-    let mut result = DVector::from_element(n + m - 1, Complex::new(0.0, 0.0));
-
-    for i in 0..(n + m - 1) {
-        let mut sum = Complex::new(0.0, 0.0);
-        for k in 0..=i {
-            if k < n && (i - k) < m {
-                sum += a[k] * b[i - k];
-            }
-        }
-        result[i] = sum;
-    }
-
-    result
-}
-
 pub fn factorial(n: usize) -> usize {
     if n == 0 {
         1
@@ -67,23 +82,116 @@ pub fn factorial(n: usize) -> usize {
     }
 }
 
-#[allow(dead_code)]
-fn correlate(a: &DVector<Complex<f64>>, b: &DVector<Complex<f64>>) -> DVector<Complex<f64>> {
-    let mut result = DVector::zeros(a.len() + b.len() - 1);
+pub fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
 
-    let a_padded = stack![DVector::zeros(b.len() - 1); a.clone(); DVector::zeros(b.len() - 1)];
-    for i in 0..result.len() {
-        for j in 0..b.len() {
-            result[i] += a_padded[i + j] * complex_conjugation(&b[j]);
-        }
+// Modified Bessel function of the first kind of order zero, by its power series.
+pub fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+    while term > f64::EPSILON * sum {
+        term *= (x / (2.0 * k)).powi(2);
+        sum += term;
+        k += 1.0;
     }
+    sum
+}
 
-    result
+fn agm(mut a: f64, mut b: f64) -> f64 {
+    while (a - b).abs() > f64::EPSILON * a {
+        (a, b) = ((a + b) / 2.0, (a * b).sqrt());
+    }
+    a
 }
 
-#[allow(dead_code)]
-fn complex_conjugation(a: &Complex<f64>) -> Complex<f64> {
-    Complex::new(a.re, -a.im)
+// Complete elliptic integral of the first kind K(m) with parameter m = k^2.
+pub fn ellipk(m: f64) -> f64 {
+    ellipkm1(1.0 - m)
+}
+
+// K(1 - p), accurate for parameters close to one.
+pub fn ellipkm1(p: f64) -> f64 {
+    if p == 0.0 {
+        return f64::INFINITY;
+    }
+    PI / (2.0 * agm(1.0, p.sqrt()))
+}
+
+// Jacobi elliptic functions (sn, cn, dn) of parameter m, by the descending Landen transformation.
+pub fn ellipj(u: f64, m: f64) -> (f64, f64, f64) {
+    if m < 1e-9 {
+        let t = u.sin();
+        let b = u.cos();
+        let ai = 0.25 * m * (u - t * b);
+        return (t - ai * b, b + ai * t, 1.0 - 0.5 * m * t * t);
+    }
+    if m >= 1.0 - 1e-10 {
+        let ai = 0.25 * (1.0 - m);
+        let b = u.cosh();
+        let t = u.tanh();
+        let phi = 1.0 / b;
+        let twon = b * u.sinh();
+        let sn = t + ai * (twon - u) / (b * b);
+        let ai = ai * t * phi;
+        return (sn, phi - ai * (twon - u), phi + ai * (twon + u));
+    }
+
+    let mut a = vec![1.0];
+    let mut c = vec![m.sqrt()];
+    let mut b = (1.0 - m).sqrt();
+    let mut twon = 1.0;
+    while (c[c.len() - 1] / a[a.len() - 1]).abs() > f64::EPSILON && a.len() < 9 {
+        let ai = a[a.len() - 1];
+        c.push((ai - b) / 2.0);
+        a.push((ai + b) / 2.0);
+        b = (ai * b).sqrt();
+        twon *= 2.0;
+    }
+
+    let mut phi = twon * a[a.len() - 1] * u;
+    let mut previous_phi = phi;
+    for i in (1..a.len()).rev() {
+        previous_phi = phi;
+        phi = ((c[i] * phi.sin() / a[i]).asin() + phi) / 2.0;
+    }
+
+    (phi.sin(), phi.cos(), phi.cos() / (phi - previous_phi).cos())
+}
+
+// Inverse of the Jacobi elliptic function sn for complex arguments, by the Landen transformation.
+pub fn arc_jacobi_sn(w: Complex<f64>, m: f64) -> Complex<f64> {
+    let complement = |k: f64| ((1.0 - k) * (1.0 + k)).sqrt();
+    let complement_complex =
+        |k: Complex<f64>| ((Complex::new(1.0, 0.0) - k) * (Complex::new(1.0, 0.0) + k)).sqrt();
+
+    let k = m.sqrt();
+    if k >= 1.0 {
+        return w.atanh();
+    }
+
+    let mut ks = vec![k];
+    while ks[ks.len() - 1] != 0.0 && ks.len() < 12 {
+        let k_complement = complement(ks[ks.len() - 1]);
+        ks.push((1.0 - k_complement) / (1.0 + k_complement));
+    }
+    let capital_k = ks.iter().skip(1).map(|k| 1.0 + k).product::<f64>() * PI / 2.0;
+
+    let w_last = ks.windows(2).fold(w, |wn, pair| {
+        2.0 * wn / ((1.0 + pair[1]) * (1.0 + complement_complex(pair[0] * wn)))
+    });
+
+    capital_k * 2.0 / PI * w_last.asin()
+}
+
+// Real inverse of the Jacobi elliptic function sc with complementary parameter 1 - m.
+pub fn arc_jacobi_sc1(w: f64, m: f64) -> f64 {
+    arc_jacobi_sn(Complex::new(0.0, w), m).im
 }
 
 #[cfg(test)]
@@ -91,6 +199,7 @@ mod tests {
     use super::*;
     use approx::assert_relative_eq;
     use nalgebra::{dmatrix, dvector};
+    use rstest::rstest;
 
     #[test]
     fn test_polynomial() {
@@ -111,6 +220,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_roots() {
+        let result = roots(&dvector![0.0, 1.0, -6.0, 11.0, -6.0, 0.0]);
+        let mut real = result.iter().map(|e| e.re).collect::<Vec<_>>();
+        real.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_relative_eq!(
+            DVector::from_vec(real),
+            dvector![0.0, 1.0, 2.0, 3.0],
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(result.map(|e| e.im), DVector::zeros(4));
+
+        let result = roots(&dvector![1.0, 0.0, 4.0]);
+        assert_relative_eq!(result.map(|e| e.re), DVector::zeros(2), epsilon = 1e-15);
+        assert_relative_eq!(
+            result.map(|e| e.im.abs()),
+            dvector![2.0, 2.0],
+            epsilon = 1e-15
+        );
+
+        assert_eq!(roots(&dvector![3.0]).len(), 0);
+    }
+
     #[test]
     fn test_characteristic_polynomial() {
         let roots = dmatrix![1.0, 0.0; 0.0, 1.0];
@@ -148,44 +280,58 @@ mod tests {
     }
 
     #[test]
-    fn test_correlate() {
-        let a = dvector![
-            Complex::new(1.0, 0.0),
-            Complex::new(2.0, 0.0),
-            Complex::new(3.0, 0.0),
-        ];
-        let b = dvector![
-            Complex::new(0.0, 0.0),
-            Complex::new(1.0, 0.0),
-            Complex::new(0.5, 0.0),
-        ];
-        let real = DVector::from_vec(correlate(&a, &b).iter().map(|e| e.re).collect::<Vec<_>>());
-        assert_relative_eq!(real, dvector![0.5, 2.0, 3.5, 3.0, 0.0]);
+    fn test_sinc() {
+        assert_relative_eq!(sinc(0.0), 1.0);
+        assert_relative_eq!(sinc(0.5), 2.0 / PI);
+        assert_relative_eq!(sinc(1.0), 0.0, epsilon = 1e-16);
+    }
 
-        let a = dvector![
-            Complex::new(1.0, 1.0),
-            Complex::new(2.0, 0.0),
-            Complex::new(3.0, -1.0),
-        ];
-        let b = dvector![
-            Complex::new(0.0, 0.0),
-            Complex::new(1.0, 0.0),
-            Complex::new(0.0, 0.5),
-        ];
-        let real = DVector::from_vec(correlate(&a, &b).iter().map(|e| e.re).collect::<Vec<_>>());
-        let imag = DVector::from_vec(correlate(&a, &b).iter().map(|e| e.im).collect::<Vec<_>>());
-        assert_relative_eq!(real, dvector![0.5, 1.0, 1.5, 3.0, 0.0]);
-        assert_relative_eq!(imag, dvector![-0.5, 0.0, -1.5, -1.0, 0.0]);
+    #[rstest]
+    #[case(0.0, 1.0)]
+    #[case(1.0, 1.266_065_877_752_008_4)]
+    #[case(5.0, 27.239_871_823_604_447)]
+    #[case(20.0, 43_558_282.559_553_53)]
+    fn test_bessel_i0(#[case] x: f64, #[case] expected: f64) {
+        assert_relative_eq!(bessel_i0(x), expected, max_relative = 1e-14);
+    }
 
-        let a = dvector![
-            Complex::new(1.0, 0.0),
-            Complex::new(2.0, 0.0),
-            Complex::new(3.0, 0.0),
-        ];
-        let b = dvector![Complex::new(4.0, 0.0), Complex::new(5.0, 0.0)];
-        let real = DVector::from_vec(correlate(&a, &b).iter().map(|e| e.re).collect::<Vec<_>>());
-        let imag = DVector::from_vec(correlate(&a, &b).iter().map(|e| e.im).collect::<Vec<_>>());
-        assert_relative_eq!(real, dvector![5.0, 14.0, 23.0, 12.0]);
-        assert_relative_eq!(imag, dvector![0.0, 0.0, 0.0, 0.0]);
+    #[test]
+    fn test_ellipk() {
+        assert_relative_eq!(ellipk(0.0), PI / 2.0);
+        assert_relative_eq!(ellipk(0.5), 1.854_074_677_301_372, epsilon = 1e-14);
+        assert_relative_eq!(ellipk(0.9), 2.578_092_113_348_173, epsilon = 1e-14);
+        assert_relative_eq!(ellipkm1(0.1), ellipk(0.9), epsilon = 1e-14);
+    }
+
+    #[rstest]
+    #[case(0.3, 0.5, 0.293412733168455, 0.955985861827787, 0.978240504174361)]
+    #[case(1.2, 0.9, 0.845178268805035, 0.534484512347855, 0.597583738521851)]
+    #[case(2.0, 0.1, 0.93284463274404, -0.360278907460091, 0.955499915811647)]
+    #[case(0.7, 0.0, f64::sin(0.7), f64::cos(0.7), 1.0)]
+    #[case(0.7, 1.0, f64::tanh(0.7), 1.0 / f64::cosh(0.7), 1.0 / f64::cosh(0.7))]
+    fn test_ellipj(
+        #[case] u: f64,
+        #[case] m: f64,
+        #[case] expected_sn: f64,
+        #[case] expected_cn: f64,
+        #[case] expected_dn: f64,
+    ) {
+        let (sn, cn, dn) = ellipj(u, m);
+        assert_relative_eq!(sn, expected_sn, epsilon = 1e-13);
+        assert_relative_eq!(cn, expected_cn, epsilon = 1e-13);
+        assert_relative_eq!(dn, expected_dn, epsilon = 1e-13);
+    }
+
+    #[test]
+    fn test_arc_jacobi_sn() {
+        let m = 0.7;
+        let (sn, _, _) = ellipj(0.4, m);
+        let u = arc_jacobi_sn(Complex::new(sn, 0.0), m);
+        assert_relative_eq!(u.re, 0.4, epsilon = 1e-13);
+        assert_relative_eq!(u.im, 0.0, epsilon = 1e-13);
+
+        // sc(u, 1 - m) = sn(u, 1 - m) / cn(u, 1 - m)
+        let (sn, cn, _) = ellipj(0.4, 1.0 - m);
+        assert_relative_eq!(arc_jacobi_sc1(sn / cn, m), 0.4, epsilon = 1e-13);
     }
 }
@@ -0,0 +1,305 @@
+use std::f64::consts::PI;
+
+use nalgebra::{Complex, DVector};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::fft::{irfft, rfft};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    Gaussian,
+    Uniform,
+}
+
+// Power spectral density proportional to 1 / f^alpha.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoiseColor {
+    // alpha = 0
+    White,
+    // alpha = 1, -3 dB per octave
+    Pink,
+    // alpha = 2, -6 dB per octave
+    Brown,
+    // alpha = -1, +3 dB per octave
+    Blue,
+    // alpha = -2, +6 dB per octave
+    Violet,
+    PowerLaw { alpha: f64 },
+}
+
+impl NoiseColor {
+    pub fn alpha(&self) -> f64 {
+        match self {
+            NoiseColor::White => 0.0,
+            NoiseColor::Pink => 1.0,
+            NoiseColor::Brown => 2.0,
+            NoiseColor::Blue => -1.0,
+            NoiseColor::Violet => -2.0,
+            NoiseColor::PowerLaw { alpha } => *alpha,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shaping {
+    // White noise filtered in the frequency domain, exact for any alpha.
+    Fft,
+    // Sum of white sources held for 1, 2, 4, ... samples. Pink only, but it needs no transform.
+    VossMcCartney,
+}
+
+// Reproducible noise: the same seed and settings give the same samples. Colored noise is
+// shaped from white noise of the chosen distribution, which makes it close to Gaussian in
+// any case. Every record is scaled to the target RMS level exactly.
+#[derive(Clone, Debug)]
+pub struct NoiseGenerator {
+    color: NoiseColor,
+    distribution: Distribution,
+    shaping: Shaping,
+    rms: f64,
+    rng: StdRng,
+}
+
+// Sources summed by Voss-McCartney, which sets the lowest frequency of the 1 / f region to
+// about 2^-ROWS of the sample rate.
+const ROWS: usize = 16;
+
+impl NoiseGenerator {
+    // White Gaussian noise with unit RMS.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            color: NoiseColor::White,
+            distribution: Distribution::Gaussian,
+            shaping: Shaping::Fft,
+            rms: 1.0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn color(mut self, color: NoiseColor) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn distribution(mut self, distribution: Distribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
+
+    pub fn shaping(mut self, shaping: Shaping) -> Self {
+        self.shaping = shaping;
+        self
+    }
+
+    pub fn rms(mut self, rms: f64) -> Self {
+        assert!(rms >= 0.0, "The RMS level must not be negative.");
+        self.rms = rms;
+        self
+    }
+
+    // The next record of n samples. Successive records are independent.
+    pub fn generate(&mut self, n: usize) -> DVector<f64> {
+        let noise = match (self.color, self.shaping) {
+            (NoiseColor::White, _) => self.white(n),
+            (_, Shaping::Fft) => self.fft_shaped(n),
+            (NoiseColor::Pink, Shaping::VossMcCartney) => self.voss_mccartney(n),
+            (_, Shaping::VossMcCartney) => {
+                panic!("Voss-McCartney shaping only produces pink noise.")
+            }
+        };
+
+        let rms = (noise.norm_squared() / n as f64).sqrt();
+        if rms > 0.0 {
+            noise * (self.rms / rms)
+        } else {
+            noise
+        }
+    }
+
+    fn white(&mut self, n: usize) -> DVector<f64> {
+        DVector::from_fn(n, |_, _| self.sample())
+    }
+
+    fn fft_shaped(&mut self, n: usize) -> DVector<f64> {
+        let spectrum = rfft(&self.white(n));
+
+        // The amplitude follows the square root of the power law. DC would be infinite for
+        // alpha > 0 and carries no slope, so it is dropped.
+        let exponent = -self.color.alpha() / 2.0;
+        let shaped = DVector::from_fn(spectrum.len(), |k, _| {
+            if k == 0 {
+                Complex::new(0.0, 0.0)
+            } else {
+                spectrum[k] * (k as f64 / n as f64).powf(exponent)
+            }
+        });
+
+        irfft(&shaped, n)
+    }
+
+    fn voss_mccartney(&mut self, n: usize) -> DVector<f64> {
+        let mut rows = (0..ROWS).map(|_| self.sample()).collect::<Vec<_>>();
+        let mut sum = rows.iter().sum::<f64>();
+
+        // Row r is redrawn every 2^r samples, staggered so that one row changes at a time
+        let noise = DVector::from_fn(n, |i, _| {
+            let row = (i + 1).trailing_zeros() as usize;
+            if row < ROWS {
+                sum -= rows[row];
+                rows[row] = self.sample();
+                sum += rows[row];
+            }
+            sum + self.sample()
+        });
+
+        noise.add_scalar(-noise.mean())
+    }
+
+    fn sample(&mut self) -> f64 {
+        match self.distribution {
+            // Box-Muller transform
+            Distribution::Gaussian => {
+                let u1 = 1.0 - self.rng.gen::<f64>();
+                let u2 = self.rng.gen::<f64>();
+                (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+            }
+            // Unit variance
+            Distribution::Uniform => self.rng.gen_range(-(3.0_f64.sqrt())..3.0_f64.sqrt()),
+        }
+    }
+}
+
+// n samples of white Gaussian noise with the given RMS level.
+pub fn white_noise(n: usize, rms: f64, seed: u64) -> DVector<f64> {
+    NoiseGenerator::new(seed).rms(rms).generate(n)
+}
+
+// n samples of 1 / f^alpha Gaussian noise with the given RMS level.
+pub fn colored_noise(n: usize, color: NoiseColor, rms: f64, seed: u64) -> DVector<f64> {
+    NoiseGenerator::new(seed).color(color).rms(rms).generate(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spectral::Welch;
+    use crate::window::Window;
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+
+    // Least-squares slope of the PSD against frequency on log-log axes, between the normalized
+    // frequencies `low` and `high`.
+    fn psd_slope(x: &DVector<f64>, low: f64, high: f64) -> f64 {
+        let psd = Welch::new(Window::Hann, 4096).estimate(x);
+        let points = psd
+            .freqs
+            .iter()
+            .zip(psd.values.iter())
+            .filter(|(f, _)| **f >= low && **f <= high)
+            .map(|(f, p)| (f.log10(), p.log10()))
+            .collect::<Vec<_>>();
+
+        let count = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+        let covariance = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum::<f64>();
+        let variance = points
+            .iter()
+            .map(|(x, _)| (x - mean_x).powi(2))
+            .sum::<f64>();
+        covariance / variance
+    }
+
+    fn kurtosis(x: &DVector<f64>) -> f64 {
+        let mean = x.mean();
+        let variance = x.map(|v| (v - mean).powi(2)).mean();
+        x.map(|v| (v - mean).powi(4)).mean() / variance.powi(2)
+    }
+
+    #[rstest]
+    #[case(NoiseColor::White)]
+    #[case(NoiseColor::Pink)]
+    #[case(NoiseColor::Brown)]
+    #[case(NoiseColor::Blue)]
+    #[case(NoiseColor::Violet)]
+    #[case(NoiseColor::PowerLaw { alpha: 0.5 })]
+    fn test_fft_shaped_slope(#[case] color: NoiseColor) {
+        let x = NoiseGenerator::new(1).color(color).generate(1 << 18);
+        assert_relative_eq!(psd_slope(&x, 0.005, 0.4), -color.alpha(), epsilon = 0.05);
+    }
+
+    #[test]
+    fn test_voss_mccartney_slope() {
+        let x = NoiseGenerator::new(2)
+            .color(NoiseColor::Pink)
+            .shaping(Shaping::VossMcCartney)
+            .generate(1 << 18);
+        // The sum of held sources only approximates 1 / f, with ripples of about a dB
+        assert_relative_eq!(psd_slope(&x, 0.002, 0.1), -1.0, epsilon = 0.1);
+    }
+
+    #[rstest]
+    #[case(NoiseColor::White, Shaping::Fft)]
+    #[case(NoiseColor::Brown, Shaping::Fft)]
+    #[case(NoiseColor::Pink, Shaping::VossMcCartney)]
+    fn test_rms_level(#[case] color: NoiseColor, #[case] shaping: Shaping) {
+        let x = NoiseGenerator::new(3)
+            .color(color)
+            .shaping(shaping)
+            .rms(0.25)
+            .generate(10_000);
+        assert_relative_eq!((x.norm_squared() / 10_000.0).sqrt(), 0.25, epsilon = 1e-12);
+    }
+
+    #[rstest]
+    #[case(Distribution::Gaussian, 3.0)]
+    #[case(Distribution::Uniform, 1.8)]
+    fn test_distribution(#[case] distribution: Distribution, #[case] expected_kurtosis: f64) {
+        let x = NoiseGenerator::new(4)
+            .distribution(distribution)
+            .generate(200_000);
+        assert_relative_eq!(x.mean(), 0.0, epsilon = 0.01);
+        assert_relative_eq!(kurtosis(&x), expected_kurtosis, epsilon = 0.05);
+        if distribution == Distribution::Uniform {
+            assert!(x.amax() <= 3.0_f64.sqrt() * 1.01);
+        }
+        assert_relative_eq!(psd_slope(&x, 0.005, 0.4), 0.0, epsilon = 0.05);
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let generator = NoiseGenerator::new(5).color(NoiseColor::Pink);
+        let mut first = generator.clone();
+        let mut second = generator.clone();
+        let record = first.generate(1000);
+        assert_eq!(record, second.generate(1000));
+        assert_ne!(record, first.generate(1000));
+        assert_ne!(
+            record,
+            NoiseGenerator::new(6)
+                .color(NoiseColor::Pink)
+                .generate(1000)
+        );
+
+        assert_eq!(white_noise(100, 2.0, 7), white_noise(100, 2.0, 7));
+        assert_eq!(
+            colored_noise(100, NoiseColor::Brown, 1.0, 7),
+            NoiseGenerator::new(7)
+                .color(NoiseColor::Brown)
+                .generate(100)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_voss_mccartney_needs_pink() {
+        NoiseGenerator::new(8)
+            .color(NoiseColor::Brown)
+            .shaping(Shaping::VossMcCartney)
+            .generate(10);
+    }
+}
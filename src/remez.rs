@@ -0,0 +1,546 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use nalgebra::DVector;
+
+use crate::fir::DiscreteFir;
+
+const MAX_ITERATIONS: usize = 25;
+const GRID_DENSITY: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RemezMode {
+    // Symmetric taps (type I for odd, type II for even lengths) with piecewise-constant gains.
+    Bandpass,
+    // Antisymmetric taps whose desired gains are slopes in gain per Hz.
+    Differentiator,
+    // Antisymmetric taps with piecewise-constant gains, usually a single band of gain 1.
+    Hilbert,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RemezError {
+    InvalidBands(String),
+    TooManyExtrema,
+    TooFewExtrema,
+    NotConverged { iterations: usize },
+}
+
+impl fmt::Display for RemezError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RemezError::InvalidBands(reason) => write!(f, "invalid bands: {}", reason),
+            RemezError::TooManyExtrema => write!(f, "the error function has too many extrema"),
+            RemezError::TooFewExtrema => write!(f, "the error function has too few extrema"),
+            RemezError::NotConverged { iterations } => {
+                write!(f, "failed to converge after {} iterations", iterations)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RemezError {}
+
+// Parks-McClellan equiripple design. `bands` holds pairs of band edges in Hz, and `desired` and
+// `weights` hold one value per band.
+pub fn remez(
+    numtaps: usize,
+    bands: &[f64],
+    desired: &[f64],
+    weights: &[f64],
+    mode: RemezMode,
+    sample_rate: f64,
+) -> Result<DiscreteFir, RemezError> {
+    validate(numtaps, bands, desired, weights, sample_rate)?;
+
+    let bands = bands.iter().map(|b| b / sample_rate).collect::<Vec<_>>();
+    let symmetric = mode == RemezMode::Bandpass;
+    let odd = numtaps % 2 == 1;
+    let r = if odd && symmetric {
+        numtaps / 2 + 1
+    } else {
+        numtaps / 2
+    };
+
+    let (grid, mut d, mut w) = dense_grid(r, numtaps, &bands, desired, weights, symmetric);
+    if grid.len() <= r {
+        return Err(RemezError::InvalidBands(
+            "the bands are too narrow for the number of taps".to_string(),
+        ));
+    }
+
+    if mode == RemezMode::Differentiator {
+        for i in 0..grid.len() {
+            d[i] *= grid[i] * sample_rate;
+            if d[i] > 1e-4 {
+                w[i] /= grid[i];
+            }
+        }
+    }
+
+    // Factor the fixed zeros of type II-IV filters out of the approximation
+    for i in 0..grid.len() {
+        let c = match (symmetric, odd) {
+            (true, true) => 1.0,
+            (true, false) => (PI * grid[i]).cos(),
+            (false, true) => (2.0 * PI * grid[i]).sin(),
+            (false, false) => (PI * grid[i]).sin(),
+        };
+        d[i] /= c;
+        w[i] *= c;
+    }
+
+    let mut extremals = (0..=r)
+        .map(|i| i * (grid.len() - 1) / r)
+        .collect::<Vec<_>>();
+
+    let mut converged = false;
+    for _ in 0..MAX_ITERATIONS {
+        let interpolation = Interpolation::new(r, &extremals, &grid, &d, &w);
+        let error = DVector::from_fn(grid.len(), |i, _| {
+            w[i] * (d[i] - interpolation.evaluate(grid[i]))
+        });
+        extremals = search(r, &error)?;
+        if is_done(&extremals, &error) {
+            converged = true;
+            break;
+        }
+    }
+    if !converged {
+        return Err(RemezError::NotConverged {
+            iterations: MAX_ITERATIONS,
+        });
+    }
+
+    let interpolation = Interpolation::new(r, &extremals, &grid, &d, &w);
+    let n = numtaps as f64;
+    let amplitudes = (0..=numtaps / 2)
+        .map(|i| {
+            let freq = i as f64 / n;
+            let c = match (symmetric, odd) {
+                (true, true) => 1.0,
+                (true, false) => (PI * freq).cos(),
+                (false, true) => (2.0 * PI * freq).sin(),
+                (false, false) => (PI * freq).sin(),
+            };
+            interpolation.evaluate(freq) * c
+        })
+        .collect::<Vec<_>>();
+
+    Ok(DiscreteFir::new(
+        frequency_sample(numtaps, &amplitudes, symmetric),
+        1.0 / sample_rate,
+    ))
+}
+
+fn validate(
+    numtaps: usize,
+    bands: &[f64],
+    desired: &[f64],
+    weights: &[f64],
+    sample_rate: f64,
+) -> Result<(), RemezError> {
+    if numtaps < 3 {
+        return Err(RemezError::InvalidBands(
+            "at least 3 taps are required".to_string(),
+        ));
+    }
+    if bands.is_empty() || !bands.len().is_multiple_of(2) {
+        return Err(RemezError::InvalidBands(
+            "bands must be given as pairs of edges".to_string(),
+        ));
+    }
+    if desired.len() != bands.len() / 2 || weights.len() != bands.len() / 2 {
+        return Err(RemezError::InvalidBands(
+            "desired and weights must have one value per band".to_string(),
+        ));
+    }
+    if bands.windows(2).any(|pair| pair[0] > pair[1])
+        || bands[0] < 0.0
+        || bands[bands.len() - 1] > sample_rate / 2.0
+    {
+        return Err(RemezError::InvalidBands(
+            "band edges must be increasing and between 0 and the Nyquist frequency".to_string(),
+        ));
+    }
+    if weights.iter().any(|w| *w <= 0.0) {
+        return Err(RemezError::InvalidBands(
+            "weights must be positive".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Frequencies are normalized so that the sample rate is 1.
+fn dense_grid(
+    r: usize,
+    numtaps: usize,
+    bands: &[f64],
+    desired: &[f64],
+    weights: &[f64],
+    symmetric: bool,
+) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let delf = 0.5 / (GRID_DENSITY * r) as f64;
+    let mut grid = vec![];
+    let mut d = vec![];
+    let mut w = vec![];
+
+    for band in 0..desired.len() {
+        // Antisymmetric filters always have a zero at DC
+        let mut low = if band == 0 && !symmetric {
+            bands[0].max(delf)
+        } else {
+            bands[2 * band]
+        };
+        let high = bands[2 * band + 1];
+        let k = (((high - low) / delf + 0.5) as usize).max(1);
+        for _ in 0..k {
+            grid.push(low);
+            d.push(desired[band]);
+            w.push(weights[band]);
+            low += delf;
+        }
+        let last = grid.len() - 1;
+        grid[last] = high;
+    }
+
+    // Type III filters also have a zero at Nyquist
+    let last = grid.len() - 1;
+    if !symmetric && numtaps % 2 == 1 && grid[last] > 0.5 - delf {
+        grid[last] = 0.5 - delf;
+    }
+
+    (grid, d, w)
+}
+
+// Barycentric Lagrange interpolation through the current extremal frequencies.
+struct Interpolation {
+    x: Vec<f64>,
+    y: Vec<f64>,
+    ad: Vec<f64>,
+}
+
+impl Interpolation {
+    fn new(r: usize, extremals: &[usize], grid: &[f64], d: &[f64], w: &[f64]) -> Self {
+        let x = extremals
+            .iter()
+            .map(|e| (2.0 * PI * grid[*e]).cos())
+            .collect::<Vec<_>>();
+
+        // Stride through the products to avoid overflow and underflow
+        let ld = (r - 1) / 15 + 1;
+        let ad = (0..=r)
+            .map(|i| {
+                let mut denom = 1.0;
+                for j in 0..ld {
+                    for k in (j..=r).step_by(ld) {
+                        if k != i {
+                            denom *= 2.0 * (x[i] - x[k]);
+                        }
+                    }
+                }
+                if denom.abs() < 1e-5 {
+                    denom = 1e-5;
+                }
+                1.0 / denom
+            })
+            .collect::<Vec<_>>();
+
+        let mut numer = 0.0;
+        let mut denom = 0.0;
+        let mut sign = 1.0;
+        for i in 0..=r {
+            numer += ad[i] * d[extremals[i]];
+            denom += sign * ad[i] / w[extremals[i]];
+            sign = -sign;
+        }
+        let delta = numer / denom;
+
+        let mut sign = 1.0;
+        let y = (0..=r)
+            .map(|i| {
+                let value = d[extremals[i]] - sign * delta / w[extremals[i]];
+                sign = -sign;
+                value
+            })
+            .collect::<Vec<_>>();
+
+        Self { x, y, ad }
+    }
+
+    fn evaluate(&self, freq: f64) -> f64 {
+        let xc = (2.0 * PI * freq).cos();
+        let mut numer = 0.0;
+        let mut denom = 0.0;
+        for i in 0..self.x.len() {
+            let c = xc - self.x[i];
+            if c.abs() < 1e-7 {
+                return self.y[i];
+            }
+            let c = self.ad[i] / c;
+            denom += c;
+            numer += c * self.y[i];
+        }
+        numer / denom
+    }
+}
+
+fn search(r: usize, error: &DVector<f64>) -> Result<Vec<usize>, RemezError> {
+    let n = error.len();
+    let mut found = vec![];
+
+    if (error[0] > 0.0 && error[0] > error[1]) || (error[0] < 0.0 && error[0] < error[1]) {
+        found.push(0);
+    }
+    for i in 1..n - 1 {
+        if (error[i] >= error[i - 1] && error[i] > error[i + 1] && error[i] > 0.0)
+            || (error[i] <= error[i - 1] && error[i] < error[i + 1] && error[i] < 0.0)
+        {
+            if found.len() >= 2 * r {
+                return Err(RemezError::TooManyExtrema);
+            }
+            found.push(i);
+        }
+    }
+    let j = n - 1;
+    if (error[j] > 0.0 && error[j] > error[j - 1]) || (error[j] < 0.0 && error[j] < error[j - 1]) {
+        if found.len() >= 2 * r {
+            return Err(RemezError::TooManyExtrema);
+        }
+        found.push(j);
+    }
+    if found.len() < r + 1 {
+        return Err(RemezError::TooFewExtrema);
+    }
+
+    // Drop extrema until exactly r + 1 alternating ones remain
+    let mut extra = found.len() - (r + 1);
+    while extra > 0 {
+        let mut up = error[found[0]] > 0.0;
+        let mut alternating = true;
+        let mut smallest = 0;
+        for j in 1..found.len() {
+            if error[found[j]].abs() < error[found[smallest]].abs() {
+                smallest = j;
+            }
+            if up && error[found[j]] < 0.0 {
+                up = false;
+            } else if !up && error[found[j]] > 0.0 {
+                up = true;
+            } else {
+                alternating = false;
+                break;
+            }
+        }
+        if alternating && extra == 1 {
+            let last = found.len() - 1;
+            smallest = if error[found[last]].abs() < error[found[0]].abs() {
+                last
+            } else {
+                0
+            };
+        }
+        found.remove(smallest);
+        extra -= 1;
+    }
+
+    Ok(found)
+}
+
+fn is_done(extremals: &[usize], error: &DVector<f64>) -> bool {
+    let magnitudes = extremals.iter().map(|e| error[*e].abs());
+    let min = magnitudes.clone().fold(f64::INFINITY, f64::min);
+    let max = magnitudes.fold(0.0, f64::max);
+
+    (max - min) / max < 1e-4
+}
+
+// Inverse DFT of the real amplitude samples into symmetric or antisymmetric taps.
+fn frequency_sample(numtaps: usize, amplitudes: &[f64], symmetric: bool) -> DVector<f64> {
+    let n = numtaps as f64;
+    let m = (n - 1.0) / 2.0;
+    let last = if numtaps % 2 == 1 {
+        (numtaps - 1) / 2
+    } else {
+        numtaps / 2 - 1
+    };
+
+    DVector::from_fn(numtaps, |i, _| {
+        let x = 2.0 * PI * (i as f64 - m) / n;
+        let mut value = if symmetric {
+            amplitudes[0]
+        } else if numtaps.is_multiple_of(2) {
+            amplitudes[numtaps / 2] * (PI * (i as f64 - m)).sin()
+        } else {
+            0.0
+        };
+        for (k, amplitude) in amplitudes.iter().enumerate().take(last + 1).skip(1) {
+            value += if symmetric {
+                2.0 * amplitude * (x * k as f64).cos()
+            } else {
+                2.0 * amplitude * (x * k as f64).sin()
+            };
+        }
+        value / n
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::Complex;
+    use rstest::rstest;
+
+    fn response(fir: &DiscreteFir, freq: f64) -> Complex<f64> {
+        fir.taps
+            .iter()
+            .enumerate()
+            .map(|(k, h)| h * Complex::new(0.0, -2.0 * PI * freq * fir.dt * k as f64).exp())
+            .sum()
+    }
+
+    fn max_deviation(fir: &DiscreteFir, low: f64, high: f64, desired: impl Fn(f64) -> f64) -> f64 {
+        (0..=2000)
+            .map(|i| low + (high - low) * i as f64 / 2000.0)
+            .map(|f| (response(fir, f).norm() - desired(f)).abs())
+            .fold(0.0, f64::max)
+    }
+
+    #[rstest]
+    #[case(51)]
+    #[case(50)]
+    fn test_remez_low_pass(#[case] numtaps: usize) {
+        let fir = remez(
+            numtaps,
+            &[0.0, 100.0, 150.0, 500.0],
+            &[1.0, 0.0],
+            &[1.0, 1.0],
+            RemezMode::Bandpass,
+            1000.0,
+        )
+        .unwrap();
+
+        for i in 0..numtaps {
+            assert_relative_eq!(fir.taps[i], fir.taps[numtaps - 1 - i], epsilon = 1e-12);
+        }
+
+        // Equal weights give equal ripple in both bands
+        let passband = max_deviation(&fir, 0.0, 100.0, |_| 1.0);
+        let stopband = max_deviation(&fir, 150.0, 500.0, |_| 0.0);
+        assert!(passband < 1e-2);
+        assert_relative_eq!(passband, stopband, max_relative = 1e-2);
+    }
+
+    #[test]
+    fn test_remez_weights_trade_ripple() {
+        let fir = remez(
+            41,
+            &[0.0, 0.2, 0.3, 0.5],
+            &[1.0, 0.0],
+            &[1.0, 10.0],
+            RemezMode::Bandpass,
+            1.0,
+        )
+        .unwrap();
+
+        let passband = max_deviation(&fir, 0.0, 0.2, |_| 1.0);
+        let stopband = max_deviation(&fir, 0.3, 0.5, |_| 0.0);
+        assert_relative_eq!(passband, 10.0 * stopband, max_relative = 5e-2);
+    }
+
+    #[test]
+    fn test_remez_multiband() {
+        let fir = remez(
+            61,
+            &[0.0, 0.08, 0.12, 0.2, 0.25, 0.35, 0.4, 0.5],
+            &[0.0, 1.0, 0.0, 0.5],
+            &[1.0, 1.0, 1.0, 1.0],
+            RemezMode::Bandpass,
+            1.0,
+        )
+        .unwrap();
+
+        assert!(max_deviation(&fir, 0.0, 0.08, |_| 0.0) < 2e-2);
+        assert!(max_deviation(&fir, 0.12, 0.2, |_| 1.0) < 2e-2);
+        assert!(max_deviation(&fir, 0.25, 0.35, |_| 0.0) < 2e-2);
+        assert!(max_deviation(&fir, 0.4, 0.5, |_| 0.5) < 2e-2);
+    }
+
+    #[rstest]
+    #[case(31)]
+    #[case(30)]
+    fn test_remez_hilbert(#[case] numtaps: usize) {
+        let fir = remez(
+            numtaps,
+            &[0.05, 0.45],
+            &[1.0],
+            &[1.0],
+            RemezMode::Hilbert,
+            1.0,
+        )
+        .unwrap();
+
+        for i in 0..numtaps {
+            assert_relative_eq!(fir.taps[i], -fir.taps[numtaps - 1 - i], epsilon = 1e-12);
+        }
+        assert!(max_deviation(&fir, 0.05, 0.45, |_| 1.0) < 1e-2);
+    }
+
+    #[test]
+    fn test_remez_differentiator() {
+        let sample_rate = 100.0;
+        let fir = remez(
+            32,
+            &[0.0, 40.0],
+            &[2.0 * PI],
+            &[1.0],
+            RemezMode::Differentiator,
+            sample_rate,
+        )
+        .unwrap();
+
+        for i in 0..32 {
+            assert_relative_eq!(fir.taps[i], -fir.taps[31 - i], epsilon = 1e-12);
+        }
+
+        // The slope is in gain per Hz, so the target here is |jw|
+        for freq in [5.0, 10.0, 20.0, 30.0, 40.0] {
+            assert_relative_eq!(
+                response(&fir, freq).norm(),
+                2.0 * PI * freq,
+                max_relative = 1e-3
+            );
+        }
+    }
+
+    #[rstest]
+    #[case(&[0.0, 0.3, 0.2, 0.5], &[1.0, 0.0], &[1.0, 1.0])]
+    #[case(&[0.0, 0.2, 0.3, 0.6], &[1.0, 0.0], &[1.0, 1.0])]
+    #[case(&[0.0, 0.2, 0.3], &[1.0, 0.0], &[1.0, 1.0])]
+    #[case(&[0.0, 0.2, 0.3, 0.5], &[1.0], &[1.0, 1.0])]
+    #[case(&[0.0, 0.2, 0.3, 0.5], &[1.0, 0.0], &[1.0, 0.0])]
+    fn test_remez_invalid_bands(
+        #[case] bands: &[f64],
+        #[case] desired: &[f64],
+        #[case] weights: &[f64],
+    ) {
+        let result = remez(21, bands, desired, weights, RemezMode::Bandpass, 1.0);
+        assert!(matches!(result, Err(RemezError::InvalidBands(_))));
+    }
+
+    #[test]
+    fn test_remez_reports_failure() {
+        // Transition bands this narrow cannot be met with so few taps
+        let result = remez(
+            9,
+            &[0.0, 0.1, 0.1001, 0.2, 0.2001, 0.3, 0.3001, 0.4, 0.4001, 0.5],
+            &[1.0, 0.0, 1.0, 0.0, 1.0],
+            &[1.0, 1.0, 1.0, 1.0, 1.0],
+            RemezMode::Bandpass,
+            1.0,
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,579 @@
+use std::f64::consts::PI;
+
+use nalgebra::DVector;
+
+// Every generator evaluates the waveform at the instants of the time vector `t`, in seconds.
+// Periodic waveforms start at zero phase at t = 0.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChirpMethod {
+    // f(t) = f0 + (f1 - f0) t / t1
+    Linear,
+    // f(t) = f0 + (f1 - f0) t^2 / t1^2
+    Quadratic,
+    // f(t) = f0 (f1 / f0)^(t / t1), the exponential sweep used for impulse response measurement.
+    Logarithmic,
+    // f(t) = f0 f1 t1 / ((f0 - f1) t + f1 t1), so that the period changes linearly.
+    Hyperbolic,
+}
+
+// A periodic excitation sampled on the time vector, with the one-sided power of each line of its
+// spectrum over one period. The powers add up to the mean square of the signal.
+#[derive(Clone, Debug)]
+pub struct Excitation {
+    pub values: DVector<f64>,
+    pub freqs: DVector<f64>,
+    pub power: DVector<f64>,
+    // In seconds.
+    pub period: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tone {
+    pub freq: f64,
+    pub amplitude: f64,
+    // In radians, relative to a sine.
+    pub phase: f64,
+}
+
+pub fn generate_sine_wave(t: &DVector<f64>, freq: f64) -> DVector<f64> {
+    t.map(|t| (2.0 * PI * freq * t).sin())
+}
+
+// +1 for the first `duty` fraction of each period and -1 for the rest.
+pub fn generate_square_wave(t: &DVector<f64>, freq: f64, duty: f64) -> DVector<f64> {
+    assert!(
+        (0.0..=1.0).contains(&duty),
+        "The duty cycle must be between 0 and 1."
+    );
+    t.map(|t| {
+        if cycle_position(t, freq) < duty {
+            1.0
+        } else {
+            -1.0
+        }
+    })
+}
+
+// Rises from -1 to 1 over the first `width` fraction of each period and falls back to -1 over
+// the rest. A width of 1 gives a rising sawtooth, 0 a falling one and 0.5 a triangle.
+pub fn generate_sawtooth_wave(t: &DVector<f64>, freq: f64, width: f64) -> DVector<f64> {
+    assert!(
+        (0.0..=1.0).contains(&width),
+        "The width must be between 0 and 1."
+    );
+    t.map(|t| {
+        let position = cycle_position(t, freq);
+        if position < width {
+            -1.0 + 2.0 * position / width
+        } else {
+            1.0 - 2.0 * (position - width) / (1.0 - width)
+        }
+    })
+}
+
+pub fn generate_triangle_wave(t: &DVector<f64>, freq: f64) -> DVector<f64> {
+    generate_sawtooth_wave(t, freq, 0.5)
+}
+
+// Sine whose frequency sweeps from f0 at t = 0 to f1 at t = t1 and keeps following the same law
+// afterwards.
+pub fn generate_chirp(
+    t: &DVector<f64>,
+    f0: f64,
+    t1: f64,
+    f1: f64,
+    method: ChirpMethod,
+) -> DVector<f64> {
+    assert!(t1 > 0.0, "The sweep time must be positive.");
+    t.map(|t| chirp_phase(t, f0, t1, f1, method).sin())
+}
+
+// Sine whose instantaneous frequency in Hz is the polynomial `poly` of t, with coefficients in
+// descending powers.
+pub fn generate_swept_sine(t: &DVector<f64>, poly: &DVector<f64>) -> DVector<f64> {
+    // The phase is 2 pi times the antiderivative of the polynomial
+    let degree = poly.len();
+    let antiderivative = DVector::from_fn(degree + 1, |i, _| {
+        if i < degree {
+            poly[i] / (degree - i) as f64
+        } else {
+            0.0
+        }
+    });
+    t.map(|t| (2.0 * PI * polyval(&antiderivative, t)).sin())
+}
+
+// Cosine at `center_freq` under a Gaussian envelope centered on t = 0. `bandwidth` is the width
+// of the spectrum at -6 dB as a fraction of the center frequency.
+pub fn generate_gaussian_pulse(t: &DVector<f64>, center_freq: f64, bandwidth: f64) -> DVector<f64> {
+    assert!(bandwidth > 0.0, "The bandwidth must be positive.");
+
+    // The spectrum exp(-(pi f)^2 / a) falls to the reference level at half the bandwidth
+    let reference = 10.0_f64.powf(-6.0 / 20.0);
+    let a = -(PI * center_freq * bandwidth).powi(2) / (4.0 * reference.ln());
+    t.map(|t| (-a * t * t).exp() * (2.0 * PI * center_freq * t).cos())
+}
+
+// 1 at the first sample at or after t0 and 0 elsewhere.
+pub fn generate_unit_impulse(t: &DVector<f64>, t0: f64) -> DVector<f64> {
+    let mut impulse = DVector::zeros(t.len());
+    if let Some(i) = t.iter().position(|t| *t >= t0) {
+        impulse[i] = 1.0;
+    }
+    impulse
+}
+
+// 0 before t0 and 1 from t0 on.
+pub fn generate_unit_step(t: &DVector<f64>, t0: f64) -> DVector<f64> {
+    t.map(|t| if t >= t0 { 1.0 } else { 0.0 })
+}
+
+pub fn generate_multitone(t: &DVector<f64>, tones: &[Tone]) -> DVector<f64> {
+    t.map(|t| {
+        tones
+            .iter()
+            .map(|tone| tone.amplitude * (2.0 * PI * tone.freq * t + tone.phase).sin())
+            .sum()
+    })
+}
+
+// Maximum-length sequence of +-amplitude from a linear feedback shift register, with one chip
+// every `clock_divider` samples of t. It repeats every 2^register_length - 1 chips, and its
+// power is spread evenly over the lines up to the chip rate.
+pub fn generate_prbs(
+    t: &DVector<f64>,
+    register_length: usize,
+    clock_divider: usize,
+    amplitude: f64,
+) -> Excitation {
+    assert!(
+        (2..=MLS_TAPS.len() + 1).contains(&register_length),
+        "The register length must be between 2 and {}.",
+        MLS_TAPS.len() + 1
+    );
+    assert!(clock_divider > 0, "The clock divider must be positive.");
+    assert!(t.len() > 1, "The time vector needs at least two samples.");
+
+    let chips = (1 << register_length) - 1;
+    let sequence = maximum_length_sequence(register_length);
+    let values = DVector::from_fn(t.len(), |i, _| {
+        if sequence[(i / clock_divider) % chips] {
+            amplitude
+        } else {
+            -amplitude
+        }
+    });
+
+    // One period of the sequence has |C_k|^2 = amplitude^2 (chips + 1) on every line but DC,
+    // shaped by the zero-order hold over clock_divider samples.
+    let dt = t[1] - t[0];
+    let samples = chips * clock_divider;
+    let power = DVector::from_fn(samples / 2 + 1, |k, _| {
+        let sequence_power = if k % chips == 0 {
+            amplitude.powi(2)
+        } else {
+            amplitude.powi(2) * (chips + 1) as f64
+        };
+        let hold = if k == 0 {
+            (clock_divider * clock_divider) as f64
+        } else {
+            ((PI * k as f64 / chips as f64).sin() / (PI * k as f64 / samples as f64).sin()).powi(2)
+        };
+        let one_sided = if k == 0 || 2 * k == samples { 1.0 } else { 2.0 };
+        one_sided * sequence_power * hold / (samples * samples) as f64
+    });
+
+    Excitation {
+        values,
+        freqs: DVector::from_fn(power.len(), |k, _| k as f64 / (samples as f64 * dt)),
+        power,
+        period: samples as f64 * dt,
+    }
+}
+
+// Equal-amplitude sines on every harmonic of 1 / period between low and high Hz, with the
+// phases of Schroeder to keep the crest factor low. The amplitudes are set for the given RMS.
+pub fn generate_schroeder_multisine(
+    t: &DVector<f64>,
+    period: f64,
+    low: f64,
+    high: f64,
+    rms: f64,
+) -> Excitation {
+    assert!(period > 0.0, "The period must be positive.");
+    let harmonics = ((low * period).ceil().max(1.0) as usize..=(high * period).floor() as usize)
+        .collect::<Vec<_>>();
+    assert!(
+        !harmonics.is_empty(),
+        "The band must contain a harmonic of 1 / period."
+    );
+
+    let count = harmonics.len() as f64;
+    let amplitude = rms * (2.0 / count).sqrt();
+    let tones = harmonics
+        .iter()
+        .enumerate()
+        .map(|(k, harmonic)| Tone {
+            freq: *harmonic as f64 / period,
+            amplitude,
+            phase: -PI * (k * (k + 1)) as f64 / count,
+        })
+        .collect::<Vec<_>>();
+
+    Excitation {
+        values: generate_multitone(t, &tones),
+        freqs: DVector::from_iterator(tones.len(), tones.iter().map(|tone| tone.freq)),
+        power: DVector::from_element(tones.len(), amplitude * amplitude / 2.0),
+        period,
+    }
+}
+
+// Feedback taps of a maximal Fibonacci register of each length from 2, counted from the output.
+const MLS_TAPS: [&[usize]; 23] = [
+    &[2, 1],
+    &[3, 2],
+    &[4, 3],
+    &[5, 3],
+    &[6, 5],
+    &[7, 6],
+    &[8, 6, 5, 4],
+    &[9, 5],
+    &[10, 7],
+    &[11, 9],
+    &[12, 11, 10, 4],
+    &[13, 12, 11, 8],
+    &[14, 13, 12, 2],
+    &[15, 14],
+    &[16, 15, 13, 4],
+    &[17, 14],
+    &[18, 11],
+    &[19, 18, 17, 14],
+    &[20, 17],
+    &[21, 19],
+    &[22, 21],
+    &[23, 18],
+    &[24, 23, 22, 17],
+];
+
+fn maximum_length_sequence(register_length: usize) -> Vec<bool> {
+    let taps = MLS_TAPS[register_length - 2];
+    let mut state = (1_u32 << register_length) - 1;
+    (0..(1 << register_length) - 1)
+        .map(|_| {
+            let output = state & 1 == 1;
+            let feedback = taps
+                .iter()
+                .fold(0, |bit, tap| bit ^ (state >> (register_length - tap)) & 1);
+            state = (state >> 1) | (feedback << (register_length - 1));
+            output
+        })
+        .collect()
+}
+
+// Fraction of the current period elapsed at time t, in [0, 1).
+fn cycle_position(t: f64, freq: f64) -> f64 {
+    (freq * t).rem_euclid(1.0)
+}
+
+fn chirp_phase(t: f64, f0: f64, t1: f64, f1: f64, method: ChirpMethod) -> f64 {
+    match method {
+        ChirpMethod::Linear => 2.0 * PI * (f0 * t + 0.5 * (f1 - f0) / t1 * t * t),
+        ChirpMethod::Quadratic => 2.0 * PI * (f0 * t + (f1 - f0) / (t1 * t1) * t.powi(3) / 3.0),
+        ChirpMethod::Logarithmic => {
+            assert!(
+                f0 * f1 > 0.0,
+                "A logarithmic chirp needs frequencies of the same sign."
+            );
+            if f0 == f1 {
+                return 2.0 * PI * f0 * t;
+            }
+            let beta = t1 / (f1 / f0).ln();
+            2.0 * PI * beta * f0 * ((f1 / f0).powf(t / t1) - 1.0)
+        }
+        ChirpMethod::Hyperbolic => {
+            assert!(
+                f0 != 0.0 && f1 != 0.0,
+                "A hyperbolic chirp needs non-zero frequencies."
+            );
+            if f0 == f1 {
+                return 2.0 * PI * f0 * t;
+            }
+            // The frequency would become infinite at this time
+            let singular = -f1 * t1 / (f0 - f1);
+            -2.0 * PI * singular * f0 * (1.0 - t / singular).abs().ln()
+        }
+    }
+}
+
+fn polyval(coeffs: &DVector<f64>, x: f64) -> f64 {
+    coeffs.iter().fold(0.0, |acc, c| acc * x + c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::rfft;
+    use crate::lti::ContinuousStateSpace;
+    use approx::assert_relative_eq;
+    use nalgebra::dmatrix;
+    use nalgebra::dvector;
+    use rstest::rstest;
+
+    fn time(sample_rate: f64, start: f64, duration: f64) -> DVector<f64> {
+        DVector::from_fn((sample_rate * duration).round() as usize, |i, _| {
+            start + i as f64 / sample_rate
+        })
+    }
+
+    #[test]
+    fn test_sine_wave() {
+        let t = dvector![0.0, 0.125, 0.25, 0.5, 0.75];
+        assert_relative_eq!(
+            generate_sine_wave(&t, 1.0),
+            dvector![0.0, 0.5_f64.sqrt(), 1.0, 0.0, -1.0],
+            epsilon = 1e-15
+        );
+    }
+
+    #[rstest]
+    #[case(0.5)]
+    #[case(0.25)]
+    #[case(0.8)]
+    fn test_square_wave_duty_cycle(#[case] duty: f64) {
+        // Half a sample of offset keeps the samples off the edges
+        let t = time(1000.0, 0.0005, 1.0);
+        let square = generate_square_wave(&t, 10.0, duty);
+        assert!(square.iter().all(|v| *v == 1.0 || *v == -1.0));
+        assert_relative_eq!(square.mean(), 2.0 * duty - 1.0, epsilon = 1e-12);
+        assert_eq!(square[0], 1.0);
+    }
+
+    #[test]
+    fn test_sawtooth_and_triangle() {
+        let t = dvector![0.0, 0.25, 0.5, 0.75, 1.25];
+        assert_relative_eq!(
+            generate_sawtooth_wave(&t, 1.0, 1.0),
+            dvector![-1.0, -0.5, 0.0, 0.5, -0.5]
+        );
+        assert_relative_eq!(
+            generate_sawtooth_wave(&t, 1.0, 0.0),
+            dvector![1.0, 0.5, 0.0, -0.5, 0.5]
+        );
+        assert_relative_eq!(
+            generate_triangle_wave(&t, 1.0),
+            dvector![-1.0, 0.0, 1.0, 0.0, 0.0]
+        );
+    }
+
+    #[rstest]
+    #[case(ChirpMethod::Linear, 205.0)]
+    #[case(ChirpMethod::Quadratic, 107.5)]
+    #[case(ChirpMethod::Logarithmic, 10.0 * 40.0_f64.sqrt())]
+    #[case(ChirpMethod::Hyperbolic, 8000.0 / 410.0)]
+    fn test_chirp_frequency(#[case] method: ChirpMethod, #[case] halfway: f64) {
+        let (f0, t1, f1) = (10.0, 2.0, 400.0);
+        let frequency = |t: f64| {
+            let h = 1e-6;
+            (chirp_phase(t + h, f0, t1, f1, method) - chirp_phase(t - h, f0, t1, f1, method))
+                / (4.0 * PI * h)
+        };
+        assert_relative_eq!(frequency(0.0), f0, max_relative = 1e-6);
+        assert_relative_eq!(frequency(1.0), halfway, max_relative = 1e-6);
+        assert_relative_eq!(frequency(t1), f1, max_relative = 1e-6);
+
+        // Without a sweep every method reduces to the sine wave
+        let t = time(1000.0, 0.0, 1.0);
+        assert_relative_eq!(
+            generate_chirp(&t, 25.0, 1.0, 25.0, method),
+            generate_sine_wave(&t, 25.0),
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn test_swept_sine() {
+        let t = time(1000.0, 0.0, 2.0);
+        assert_relative_eq!(
+            generate_swept_sine(&t, &dvector![20.0, 5.0]),
+            generate_chirp(&t, 5.0, 1.0, 25.0, ChirpMethod::Linear),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            generate_swept_sine(&t, &dvector![3.0]),
+            generate_sine_wave(&t, 3.0),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_gaussian_pulse_bandwidth() {
+        let sample_rate = 10_000.0;
+        let t = time(sample_rate, -0.05, 0.1);
+        let pulse = generate_gaussian_pulse(&t, 1000.0, 0.5);
+        assert_relative_eq!(pulse[500], 1.0);
+        assert!(pulse[0].abs() < 1e-12 && pulse[999].abs() < 1e-12);
+
+        // Bins are 10 Hz apart, and the -6 dB points are at 750 and 1250 Hz
+        let spectrum = rfft(&pulse).map(|c| c.norm());
+        let level = |bin: usize| 20.0 * (spectrum[bin] / spectrum[100]).log10();
+        assert_relative_eq!(level(75), -6.0, epsilon = 0.05);
+        assert_relative_eq!(level(125), -6.0, epsilon = 0.05);
+    }
+
+    #[test]
+    fn test_impulse_and_step() {
+        let t = time(10.0, 0.0, 1.0);
+        let impulse = generate_unit_impulse(&t, 0.25);
+        let step = generate_unit_step(&t, 0.25);
+        assert_eq!(
+            step,
+            dvector![0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0]
+        );
+        assert_eq!(impulse.sum(), 1.0);
+        assert_eq!(impulse[3], 1.0);
+
+        assert_eq!(generate_unit_impulse(&t, 2.0), DVector::zeros(10));
+        assert_eq!(generate_unit_step(&t, -1.0), DVector::from_element(10, 1.0));
+    }
+
+    #[test]
+    fn test_multitone() {
+        let t = time(1000.0, 0.0, 1.0);
+        let tones = [
+            Tone {
+                freq: 50.0,
+                amplitude: 1.0,
+                phase: 0.0,
+            },
+            Tone {
+                freq: 120.0,
+                amplitude: 0.5,
+                phase: PI / 2.0,
+            },
+        ];
+        let signal = generate_multitone(&t, &tones);
+        let expected = generate_sine_wave(&t, 50.0) + t.map(|t| 0.5 * (2.0 * PI * 120.0 * t).cos());
+        assert_relative_eq!(signal, expected, epsilon = 1e-12);
+        assert_eq!(generate_multitone(&t, &[]), DVector::zeros(1000));
+    }
+
+    // One-sided power of each bin of a record holding exactly one period.
+    fn line_power(x: &DVector<f64>) -> DVector<f64> {
+        let n = x.len();
+        let spectrum = rfft(x);
+        DVector::from_fn(spectrum.len(), |k, _| {
+            let one_sided = if k == 0 || 2 * k == n { 1.0 } else { 2.0 };
+            one_sided * spectrum[k].norm_sqr() / (n * n) as f64
+        })
+    }
+
+    #[rstest]
+    #[case(2)]
+    #[case(5)]
+    #[case(10)]
+    #[case(16)]
+    #[case(24)]
+    fn test_maximum_length_sequence(#[case] register_length: usize) {
+        let sequence = maximum_length_sequence(register_length);
+        let chips = (1 << register_length) - 1;
+        assert_eq!(sequence.len(), chips);
+        assert_eq!(
+            sequence.iter().filter(|bit| **bit).count(),
+            1 << (register_length - 1)
+        );
+
+        // The circular autocorrelation of the +-1 sequence is N at lag 0 and -1 elsewhere
+        if register_length <= 10 {
+            let levels = sequence
+                .iter()
+                .map(|bit| if *bit { 1.0 } else { -1.0 })
+                .collect::<Vec<f64>>();
+            for lag in 0..chips {
+                let correlation = (0..chips)
+                    .map(|i| levels[i] * levels[(i + lag) % chips])
+                    .sum::<f64>();
+                assert_eq!(correlation, if lag == 0 { chips as f64 } else { -1.0 });
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(4, 1)]
+    #[case(5, 3)]
+    #[case(7, 4)]
+    fn test_prbs(#[case] register_length: usize, #[case] clock_divider: usize) {
+        let sample_rate = 1000.0;
+        let samples = ((1 << register_length) - 1) * clock_divider;
+        let t = time(sample_rate, 0.0, 2.0 * samples as f64 / sample_rate);
+        let prbs = generate_prbs(&t, register_length, clock_divider, 0.5);
+
+        assert!(prbs.values.iter().all(|v| v.abs() == 0.5));
+        assert_relative_eq!(prbs.period, samples as f64 / sample_rate, epsilon = 1e-12);
+        assert_eq!(
+            prbs.values.rows(0, samples),
+            prbs.values.rows(samples, samples)
+        );
+        for i in 0..samples {
+            assert_eq!(
+                prbs.values[i],
+                prbs.values[i / clock_divider * clock_divider]
+            );
+        }
+
+        let one_period = prbs.values.rows(0, samples).into_owned();
+        assert_relative_eq!(prbs.power, line_power(&one_period), epsilon = 1e-12);
+        assert_relative_eq!(prbs.power.sum(), 0.25, epsilon = 1e-12);
+        assert_relative_eq!(prbs.freqs[1], 1.0 / prbs.period, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_schroeder_multisine() {
+        let sample_rate = 1000.0;
+        let t = time(sample_rate, 0.0, 1.0);
+        let multisine = generate_schroeder_multisine(&t, 1.0, 10.0, 200.0, 0.3);
+
+        assert_eq!(multisine.freqs.len(), 191);
+        assert_relative_eq!(multisine.freqs[0], 10.0);
+        assert_relative_eq!(multisine.power.sum(), 0.09, epsilon = 1e-12);
+
+        let power = line_power(&multisine.values);
+        assert_relative_eq!(
+            (multisine.values.norm_squared() / 1000.0).sqrt(),
+            0.3,
+            epsilon = 1e-9
+        );
+        for (freq, expected) in multisine.freqs.iter().zip(multisine.power.iter()) {
+            assert_relative_eq!(power[*freq as usize], expected, epsilon = 1e-12);
+        }
+
+        // Zero phases would give a crest factor of sqrt(2 * 191) = 19.5
+        let crest_factor = multisine.values.amax() / 0.3;
+        assert!(crest_factor < 2.0);
+    }
+
+    #[test]
+    fn test_excitation_identifies_plant() {
+        // First order low-pass with a corner at 20 Hz
+        let corner = 2.0 * PI * 20.0;
+        let mut plant = ContinuousStateSpace::new(
+            dmatrix![-corner],
+            dmatrix![corner],
+            dmatrix![1.0],
+            dmatrix![0.0],
+        );
+        let sample_rate = 10_000.0;
+        let t = time(sample_rate, 0.0, 3.0);
+        let multisine = generate_schroeder_multisine(&t, 0.5, 2.0, 100.0, 1.0);
+        let outputs = plant.simulate(multisine.values.clone(), t.clone());
+
+        // The last period is in steady state, so the output lines follow |H|^2
+        let samples = (multisine.period * sample_rate) as usize;
+        let output_power = line_power(&outputs.rows(t.len() - samples, samples).into_owned());
+        for (freq, input_power) in multisine.freqs.iter().zip(multisine.power.iter()) {
+            let gain = output_power[(freq * multisine.period).round() as usize] / input_power;
+            let expected = 1.0 / (1.0 + (2.0 * PI * freq / corner).powi(2));
+            assert_relative_eq!(gain, expected, max_relative = 1e-3);
+        }
+    }
+}
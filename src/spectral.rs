@@ -0,0 +1,562 @@
+use std::f64::consts::PI;
+
+use nalgebra::{Complex, DVector};
+
+use crate::fft::{fft, fftfreq, rfft, rfftfreq};
+use crate::lti::DiscreteTransferFunction;
+use crate::window::Window;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Detrend {
+    None,
+    Constant,
+    Linear,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sides {
+    // Frequencies from 0 to Nyquist, with the power of the negative frequencies folded in.
+    OneSided,
+    // All frequencies in the order of fftfreq.
+    TwoSided,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scaling {
+    // Power spectral density in units^2 / Hz.
+    Density,
+    // Power spectrum in units^2, so that a sinusoid on a bin reads its mean square.
+    Spectrum,
+}
+
+#[derive(Clone, Debug)]
+pub struct Psd {
+    pub freqs: DVector<f64>,
+    pub values: DVector<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CrossSpectrum {
+    pub freqs: DVector<f64>,
+    pub values: DVector<Complex<f64>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Estimator {
+    // Pxy / Pxx, unbiased by noise on the output.
+    H1,
+    // Pyy / Pyx, unbiased by noise on the input.
+    H2,
+}
+
+#[derive(Clone, Debug)]
+pub struct FrequencyResponse {
+    pub freqs: DVector<f64>,
+    pub values: DVector<Complex<f64>>,
+}
+
+// Deviation of an estimated response from a model, per frequency.
+#[derive(Clone, Debug)]
+pub struct ResponseComparison {
+    pub freqs: DVector<f64>,
+    pub expected: DVector<Complex<f64>>,
+    pub magnitude_error_db: DVector<f64>,
+    // Wrapped to [-pi, pi].
+    pub phase_error: DVector<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Welch {
+    window: Window,
+    segment_length: usize,
+    overlap: usize,
+    fft_length: usize,
+    detrend: Detrend,
+    sides: Sides,
+    scaling: Scaling,
+    sample_rate: f64,
+}
+
+impl Welch {
+    // Half-overlapping segments with a periodic window, constant detrending and a one-sided
+    // density at a sample rate of 1.
+    pub fn new(window: Window, segment_length: usize) -> Self {
+        Self {
+            window,
+            segment_length,
+            overlap: segment_length / 2,
+            fft_length: segment_length,
+            detrend: Detrend::Constant,
+            sides: Sides::OneSided,
+            scaling: Scaling::Density,
+            sample_rate: 1.0,
+        }
+    }
+
+    pub fn overlap(mut self, overlap: usize) -> Self {
+        assert!(
+            overlap < self.segment_length,
+            "The overlap must be shorter than a segment."
+        );
+        self.overlap = overlap;
+        self
+    }
+
+    pub fn fft_length(mut self, fft_length: usize) -> Self {
+        assert!(
+            fft_length >= self.segment_length,
+            "The FFT length must be at least the segment length."
+        );
+        self.fft_length = fft_length;
+        self
+    }
+
+    pub fn detrend(mut self, detrend: Detrend) -> Self {
+        self.detrend = detrend;
+        self
+    }
+
+    pub fn sides(mut self, sides: Sides) -> Self {
+        self.sides = sides;
+        self
+    }
+
+    pub fn scaling(mut self, scaling: Scaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    // Averages the modified periodograms of the segments. Segments that would run past the end
+    // of the signal are dropped, and a signal shorter than a segment is used whole.
+    pub fn estimate(&self, x: &DVector<f64>) -> Psd {
+        let csd = self.csd(x, x);
+        Psd {
+            freqs: csd.freqs,
+            values: csd.values.map(|c| c.re),
+        }
+    }
+
+    // Cross-spectral density conj(X) Y, averaged over segments as in estimate.
+    pub fn csd(&self, x: &DVector<f64>, y: &DVector<f64>) -> CrossSpectrum {
+        assert_eq!(x.len(), y.len(), "The signals must have the same length.");
+        assert!(!x.is_empty(), "The signals must not be empty.");
+        let segment_length = self.segment_length.min(x.len());
+        let fft_length = self.fft_length.max(segment_length);
+        let step = segment_length - self.overlap.min(segment_length - 1);
+        let segments = 1 + (x.len() - segment_length) / step;
+
+        let window = self.window.periodic(segment_length);
+        let scale = match self.scaling {
+            Scaling::Density => 1.0 / (self.sample_rate * window.norm_squared()),
+            Scaling::Spectrum => 1.0 / window.sum().powi(2),
+        };
+
+        let transform = |signal: &DVector<f64>, start: usize| {
+            let segment = detrend(
+                &signal.rows(start, segment_length).into_owned(),
+                self.detrend,
+            );
+            let mut frame = DVector::zeros(fft_length);
+            frame
+                .rows_mut(0, segment_length)
+                .copy_from(&segment.component_mul(&window));
+            match self.sides {
+                Sides::OneSided => rfft(&frame),
+                Sides::TwoSided => fft(&frame),
+            }
+        };
+
+        let bins = match self.sides {
+            Sides::OneSided => fft_length / 2 + 1,
+            Sides::TwoSided => fft_length,
+        };
+        let mut values = DVector::<Complex<f64>>::zeros(bins);
+        for k in 0..segments {
+            let x_spectrum = transform(x, k * step);
+            let y_spectrum = transform(y, k * step);
+            values += x_spectrum.zip_map(&y_spectrum, |a, b| a.conj() * b);
+        }
+        values *= Complex::new(scale / segments as f64, 0.0);
+
+        // Fold in the negative frequencies, which exist for every bin but DC and Nyquist
+        if self.sides == Sides::OneSided {
+            let last = if fft_length.is_multiple_of(2) {
+                bins - 1
+            } else {
+                bins
+            };
+            for i in 1..last {
+                values[i] *= 2.0;
+            }
+        }
+
+        let freqs = match self.sides {
+            Sides::OneSided => rfftfreq(fft_length, 1.0 / self.sample_rate),
+            Sides::TwoSided => fftfreq(fft_length, 1.0 / self.sample_rate),
+        };
+        CrossSpectrum { freqs, values }
+    }
+
+    // Magnitude-squared coherence |Pxy|^2 / (Pxx Pyy), between 0 and 1.
+    pub fn coherence(&self, x: &DVector<f64>, y: &DVector<f64>) -> Psd {
+        let pxx = self.estimate(x);
+        let pyy = self.estimate(y);
+        let pxy = self.csd(x, y);
+        Psd {
+            freqs: pxy.freqs,
+            values: DVector::from_fn(pxy.values.len(), |i, _| {
+                pxy.values[i].norm_sqr() / (pxx.values[i] * pyy.values[i])
+            }),
+        }
+    }
+
+    // Frequency response from input x to output y.
+    pub fn transfer_function(
+        &self,
+        x: &DVector<f64>,
+        y: &DVector<f64>,
+        estimator: Estimator,
+    ) -> FrequencyResponse {
+        let pxy = self.csd(x, y);
+        let values = match estimator {
+            Estimator::H1 => {
+                let pxx = self.estimate(x);
+                pxy.values.zip_map(&pxx.values, |pxy, pxx| pxy / pxx)
+            }
+            Estimator::H2 => {
+                let pyy = self.estimate(y);
+                pxy.values.zip_map(&pyy.values, |pxy, pyy| pyy / pxy.conj())
+            }
+        };
+        FrequencyResponse {
+            freqs: pxy.freqs,
+            values,
+        }
+    }
+}
+
+// One-sided density of the whole signal with a rectangular window and the mean removed.
+pub fn periodogram(x: &DVector<f64>, sample_rate: f64) -> Psd {
+    assert!(!x.is_empty(), "The signals must not be empty.");
+    Welch::new(Window::Rectangular, x.len())
+        .overlap(0)
+        .sample_rate(sample_rate)
+        .estimate(x)
+}
+
+// One-sided density averaged over half-overlapping Hann-windowed segments.
+pub fn welch(x: &DVector<f64>, segment_length: usize, sample_rate: f64) -> Psd {
+    Welch::new(Window::Hann, segment_length)
+        .sample_rate(sample_rate)
+        .estimate(x)
+}
+
+pub fn csd(
+    x: &DVector<f64>,
+    y: &DVector<f64>,
+    segment_length: usize,
+    sample_rate: f64,
+) -> CrossSpectrum {
+    Welch::new(Window::Hann, segment_length)
+        .sample_rate(sample_rate)
+        .csd(x, y)
+}
+
+pub fn coherence(
+    x: &DVector<f64>,
+    y: &DVector<f64>,
+    segment_length: usize,
+    sample_rate: f64,
+) -> Psd {
+    Welch::new(Window::Hann, segment_length)
+        .sample_rate(sample_rate)
+        .coherence(x, y)
+}
+
+// Evaluates the transfer function at the frequencies of the estimate, which are in Hz.
+pub fn compare_with_transfer_function(
+    estimate: &FrequencyResponse,
+    tf: &DiscreteTransferFunction,
+) -> ResponseComparison {
+    let expected = tf.freq_response(&(2.0 * PI * &estimate.freqs));
+
+    let ratio = estimate
+        .values
+        .zip_map(&expected, |estimate, expected| estimate / expected);
+    ResponseComparison {
+        freqs: estimate.freqs.clone(),
+        expected,
+        magnitude_error_db: ratio.map(|r| 20.0 * r.norm().log10()),
+        phase_error: ratio.map(|r| r.arg()),
+    }
+}
+
+pub fn detrend(x: &DVector<f64>, detrend: Detrend) -> DVector<f64> {
+    let n = x.len();
+    match detrend {
+        Detrend::None => x.clone(),
+        Detrend::Constant => x.add_scalar(-x.mean()),
+        // Least-squares line through the samples against their indices
+        Detrend::Linear => {
+            if n < 2 {
+                return x.add_scalar(-x.mean());
+            }
+            let t = DVector::from_fn(n, |i, _| i as f64);
+            let t_mean = t.mean();
+            let x_mean = x.mean();
+            let slope = (t.add_scalar(-t_mean)).dot(&x.add_scalar(-x_mean))
+                / t.add_scalar(-t_mean).norm_squared();
+            DVector::from_fn(n, |i, _| x[i] - x_mean - slope * (t[i] - t_mean))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter_design::BandType;
+    use approx::assert_relative_eq;
+    use nalgebra::dvector;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use rstest::rstest;
+
+    fn sine(n: usize, freq: f64, amplitude: f64, sample_rate: f64) -> DVector<f64> {
+        DVector::from_fn(n, |i, _| {
+            amplitude * (2.0 * PI * freq * i as f64 / sample_rate).sin()
+        })
+    }
+
+    #[test]
+    fn test_detrend() {
+        let x = dvector![1.0, 3.0, 5.0, 7.0];
+        assert_relative_eq!(detrend(&x, Detrend::None), x);
+        assert_relative_eq!(
+            detrend(&x, Detrend::Constant),
+            dvector![-3.0, -1.0, 1.0, 3.0]
+        );
+        assert_relative_eq!(
+            detrend(&x, Detrend::Linear),
+            DVector::zeros(4),
+            epsilon = 1e-14
+        );
+    }
+
+    #[rstest]
+    #[case(1000)]
+    #[case(999)]
+    fn test_periodogram_parseval(#[case] n: usize) {
+        let mut rng = StdRng::seed_from_u64(0);
+        let x = DVector::<f64>::from_fn(n, |_, _| rng.gen());
+        let sample_rate = 200.0;
+        let psd = periodogram(&x, sample_rate);
+
+        // The density integrates to the variance
+        let df = sample_rate / n as f64;
+        let variance = detrend(&x, Detrend::Constant).norm_squared() / n as f64;
+        assert_relative_eq!(psd.values.sum() * df, variance, epsilon = 1e-12);
+        assert_relative_eq!(psd.freqs, rfftfreq(n, 1.0 / sample_rate));
+    }
+
+    #[test]
+    fn test_periodogram_two_sided() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let x = DVector::<f64>::from_fn(64, |_, _| rng.gen());
+        let one_sided = periodogram(&x, 1.0);
+        let two_sided = Welch::new(Window::Rectangular, 64)
+            .overlap(0)
+            .sides(Sides::TwoSided)
+            .estimate(&x);
+
+        assert_relative_eq!(two_sided.freqs, fftfreq(64, 1.0));
+        assert_relative_eq!(two_sided.values[0], one_sided.values[0], epsilon = 1e-12);
+        assert_relative_eq!(
+            two_sided.values[5] + two_sided.values[59],
+            one_sided.values[5],
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(two_sided.values[32], one_sided.values[32], epsilon = 1e-12);
+    }
+
+    #[rstest]
+    #[case(Window::Rectangular)]
+    #[case(Window::Hann)]
+    #[case(Window::FlatTop)]
+    fn test_spectrum_scaling_reads_sine_power(#[case] window: Window) {
+        // 100 Hz falls on a bin of the 256-point segments
+        let sample_rate = 1024.0;
+        let amplitude = 2.0;
+        let x = sine(4096, 100.0, amplitude, sample_rate);
+        let psd = Welch::new(window, 256)
+            .scaling(Scaling::Spectrum)
+            .sample_rate(sample_rate)
+            .estimate(&x);
+
+        let peak = psd.values.argmax().0;
+        assert_relative_eq!(psd.freqs[peak], 100.0);
+        assert_relative_eq!(
+            psd.values[peak],
+            amplitude * amplitude / 2.0,
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn test_welch_white_noise_level() {
+        let sample_rate = 100.0;
+        let std = 0.5;
+        let mut rng = StdRng::seed_from_u64(2);
+        let x = DVector::<f64>::from_fn(100_000, |_, _| {
+            // Uniform noise with the given standard deviation
+            std * 12.0_f64.sqrt() * (rng.gen::<f64>() - 0.5)
+        });
+        let psd = welch(&x, 256, sample_rate);
+
+        // A one-sided white density is 2 sigma^2 / fs
+        let level = 2.0 * std * std / sample_rate;
+        let interior = psd.values.rows(1, psd.values.len() - 2);
+        assert_relative_eq!(interior.mean(), level, max_relative = 2e-2);
+        assert!(interior.iter().all(|p| (p - level).abs() < 0.3 * level));
+    }
+
+    #[test]
+    fn test_welch_fft_length_and_detrend() {
+        let sample_rate = 10.0;
+        let ramp = DVector::from_fn(1000, |i, _| 0.01 * i as f64);
+        let x = sine(1000, 2.0, 1.0, sample_rate) + ramp;
+        let psd = Welch::new(Window::Hann, 100)
+            .fft_length(200)
+            .detrend(Detrend::Linear)
+            .sample_rate(sample_rate)
+            .estimate(&x);
+
+        assert_eq!(psd.values.len(), 101);
+        assert_relative_eq!(psd.freqs[psd.values.argmax().0], 2.0);
+
+        // Without detrending the ramp leaks into the lowest bins
+        let raw = Welch::new(Window::Hann, 100)
+            .detrend(Detrend::None)
+            .sample_rate(sample_rate)
+            .estimate(&x);
+        assert!(raw.values[0] > 100.0 * psd.values[0]);
+    }
+
+    fn filtered_noise(
+        tf: &mut DiscreteTransferFunction,
+        n: usize,
+        output_noise: f64,
+        seed: u64,
+    ) -> (DVector<f64>, DVector<f64>) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let x = DVector::<f64>::from_fn(n, |_, _| rng.gen::<f64>() - 0.5);
+        let t = DVector::from_fn(n, |i, _| i as f64 * tf.dt);
+        let y = tf.simulate(x.clone(), t)
+            + DVector::<f64>::from_fn(n, |_, _| output_noise * (rng.gen::<f64>() - 0.5));
+        (x, y)
+    }
+
+    #[test]
+    fn test_csd_of_signal_with_itself_is_psd() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let x = DVector::<f64>::from_fn(2000, |_, _| rng.gen());
+        let welch = Welch::new(Window::Hann, 128).sample_rate(50.0);
+        let psd = welch.estimate(&x);
+        let csd = welch.csd(&x, &x);
+
+        assert_relative_eq!(csd.values.map(|c| c.re), psd.values, epsilon = 1e-12);
+        assert_relative_eq!(
+            csd.values.map(|c| c.im),
+            DVector::zeros(65),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(csd.freqs, psd.freqs);
+    }
+
+    #[test]
+    fn test_csd_of_delayed_signal() {
+        // y[n] = x[n - 1] has Pxy = Pxx exp(-i w)
+        let mut rng = StdRng::seed_from_u64(4);
+        let x = DVector::<f64>::from_fn(4097, |_, _| rng.gen());
+        let y = x.rows(0, 4096).into_owned();
+        let x = x.rows(1, 4096).into_owned();
+        let result = csd(&y, &x, 256, 1.0);
+
+        for i in 1..20 {
+            let expected = -2.0 * PI * result.freqs[i];
+            assert_relative_eq!(result.values[i].arg(), -expected, epsilon = 1e-1);
+        }
+    }
+
+    #[test]
+    fn test_transfer_function_estimate() {
+        let sample_rate = 1000.0;
+        let mut tf =
+            DiscreteTransferFunction::butter(4, 100.0, 1.0 / sample_rate, BandType::LowPass);
+        let (x, y) = filtered_noise(&mut tf, 200_000, 0.0, 5);
+        let welch = Welch::new(Window::Hann, 512).sample_rate(sample_rate);
+
+        // Without noise both estimators agree with the model and the coherence is 1
+        for estimator in [Estimator::H1, Estimator::H2] {
+            let estimate = welch.transfer_function(&x, &y, estimator);
+            let comparison = compare_with_transfer_function(&estimate, &tf);
+
+            // Detrending empties the DC bin
+            for i in 1..estimate.freqs.len() {
+                if estimate.freqs[i] < 150.0 {
+                    assert!(comparison.magnitude_error_db[i].abs() < 0.1);
+                    assert!(comparison.phase_error[i].abs() < 0.02);
+                }
+            }
+        }
+
+        let coherence = welch.coherence(&x, &y);
+        for i in 1..coherence.freqs.len() {
+            if coherence.freqs[i] < 150.0 {
+                assert_relative_eq!(coherence.values[i], 1.0, epsilon = 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_transfer_function_estimate_with_output_noise() {
+        let sample_rate = 1000.0;
+        let mut tf =
+            DiscreteTransferFunction::butter(4, 100.0, 1.0 / sample_rate, BandType::LowPass);
+        let (x, y) = filtered_noise(&mut tf, 200_000, 0.5, 6);
+        let welch = Welch::new(Window::Hann, 512).sample_rate(sample_rate);
+
+        let h1 =
+            compare_with_transfer_function(&welch.transfer_function(&x, &y, Estimator::H1), &tf);
+        let h2 =
+            compare_with_transfer_function(&welch.transfer_function(&x, &y, Estimator::H2), &tf);
+        let coherence = welch.coherence(&x, &y);
+
+        // H1 stays unbiased where H2 overestimates the gain, and the noise lowers the coherence
+        let bin = 250 * 512 / 1000;
+        assert!(h1
+            .magnitude_error_db
+            .rows(1, 50)
+            .iter()
+            .all(|e| e.abs() < 0.5));
+        assert!(h2.magnitude_error_db[bin] > 3.0);
+        assert!(coherence.values[bin] < 0.5);
+        assert!(coherence.values.iter().all(|c| *c <= 1.0 + 1e-12));
+    }
+
+    #[test]
+    #[should_panic(expected = "The signals must not be empty.")]
+    fn test_welch_empty_signal() {
+        Welch::new(Window::Hann, 16).estimate(&DVector::zeros(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "The signals must not be empty.")]
+    fn test_periodogram_empty_signal() {
+        periodogram(&DVector::zeros(0), 1.0);
+    }
+}
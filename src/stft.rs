@@ -0,0 +1,303 @@
+use nalgebra::{Complex, DMatrix, DVector};
+
+use crate::fft::{irfft, rfft, rfftfreq};
+use crate::window::Window;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Padding {
+    // Frames start at the first sample.
+    None,
+    // Frames are centred on multiples of the hop, with half a window of zeros at both ends.
+    Zeros,
+    // Frames are centred as for Zeros, with the signal reflected about its end samples.
+    Reflect,
+}
+
+#[derive(Clone, Debug)]
+pub struct Spectrogram {
+    // One-sided spectra, one row per frequency and one column per frame.
+    pub values: DMatrix<Complex<f64>>,
+    // Centre of each frame in seconds.
+    pub times: DVector<f64>,
+    pub freqs: DVector<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Stft {
+    window: DVector<f64>,
+    hop: usize,
+    fft_length: usize,
+    padding: Padding,
+    sample_rate: f64,
+}
+
+impl Stft {
+    // Periodic window with a hop of half the window, no zero-padding in the FFT, reflected
+    // ends and a sample rate of 1.
+    pub fn new(window: Window, window_length: usize) -> Self {
+        Self {
+            window: window.periodic(window_length),
+            hop: (window_length / 2).max(1),
+            fft_length: window_length,
+            padding: Padding::Reflect,
+            sample_rate: 1.0,
+        }
+    }
+
+    pub fn hop(mut self, hop: usize) -> Self {
+        assert!(hop > 0, "The hop must be positive.");
+        self.hop = hop;
+        self
+    }
+
+    pub fn fft_length(mut self, fft_length: usize) -> Self {
+        assert!(
+            fft_length >= self.window.len(),
+            "The FFT length must be at least the window length."
+        );
+        self.fft_length = fft_length;
+        self
+    }
+
+    pub fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    pub fn stft(&self, x: &DVector<f64>) -> Spectrogram {
+        let window_length = self.window.len();
+        let padded = self.pad(x);
+        let frames = self.frames(padded.len());
+
+        let mut values = DMatrix::zeros(self.fft_length / 2 + 1, frames);
+        let mut frame = DVector::zeros(self.fft_length);
+        for k in 0..frames {
+            let start = k * self.hop;
+            for i in 0..window_length {
+                frame[i] = if start + i < padded.len() {
+                    padded[start + i] * self.window[i]
+                } else {
+                    0.0
+                };
+            }
+            values.set_column(k, &rfft(&frame));
+        }
+
+        let offset = match self.padding {
+            Padding::None => window_length as f64 / 2.0,
+            Padding::Zeros | Padding::Reflect => 0.0,
+        };
+        Spectrogram {
+            values,
+            times: DVector::from_fn(frames, |k, _| {
+                (k as f64 * self.hop as f64 + offset) / self.sample_rate
+            }),
+            freqs: rfftfreq(self.fft_length, 1.0 / self.sample_rate),
+        }
+    }
+
+    // Weighted overlap-add: each frame is windowed again and the sum is divided by the summed
+    // squared windows, which recovers the signal wherever the NOLA condition holds.
+    pub fn istft(&self, values: &DMatrix<Complex<f64>>, length: usize) -> DVector<f64> {
+        let window_length = self.window.len();
+        let frames = values.ncols();
+        let total = (frames.max(1) - 1) * self.hop + window_length;
+
+        let mut output = DVector::zeros(total);
+        let mut norm = DVector::<f64>::zeros(total);
+        for k in 0..frames {
+            let frame = irfft(&values.column(k).into_owned(), self.fft_length);
+            let start = k * self.hop;
+            for i in 0..window_length {
+                output[start + i] += frame[i] * self.window[i];
+                norm[start + i] += self.window[i] * self.window[i];
+            }
+        }
+        for i in 0..total {
+            if norm[i] > 1e-10 {
+                output[i] /= norm[i];
+            }
+        }
+
+        let offset = self.left_padding();
+        DVector::from_fn(length, |i, _| {
+            if offset + i < total {
+                output[offset + i]
+            } else {
+                0.0
+            }
+        })
+    }
+
+    fn left_padding(&self) -> usize {
+        match self.padding {
+            Padding::None => 0,
+            Padding::Zeros | Padding::Reflect => self.window.len() / 2,
+        }
+    }
+
+    fn pad(&self, x: &DVector<f64>) -> DVector<f64> {
+        let n = x.len();
+        let pad = self.left_padding();
+        match self.padding {
+            Padding::None => x.clone(),
+            Padding::Zeros => DVector::from_fn(n + 2 * pad, |i, _| {
+                if i >= pad && i < n + pad {
+                    x[i - pad]
+                } else {
+                    0.0
+                }
+            }),
+            Padding::Reflect => {
+                assert!(
+                    n > pad,
+                    "Reflect padding needs more samples than half a window."
+                );
+                DVector::from_fn(n + 2 * pad, |i, _| {
+                    if i < pad {
+                        x[pad - i]
+                    } else if i < n + pad {
+                        x[i - pad]
+                    } else {
+                        x[2 * (n - 1) + pad - i]
+                    }
+                })
+            }
+        }
+    }
+
+    // Enough frames to cover every sample, with the last one zero-padded if needed.
+    fn frames(&self, length: usize) -> usize {
+        let window_length = self.window.len();
+        if length <= window_length {
+            1
+        } else {
+            1 + (length - window_length).div_ceil(self.hop)
+        }
+    }
+}
+
+// Constant overlap-add: the shifted windows sum to a constant.
+pub fn check_cola(window: &DVector<f64>, hop: usize, tolerance: f64) -> bool {
+    let sums = overlap_sums(window, hop, |w| w);
+    let mean = sums.mean();
+    sums.iter().all(|sum| (sum - mean).abs() < tolerance)
+}
+
+// Nonzero overlap-add: the shifted squared windows never sum to zero, so that the weighted
+// overlap-add of istft is invertible.
+pub fn check_nola(window: &DVector<f64>, hop: usize, tolerance: f64) -> bool {
+    overlap_sums(window, hop, |w| w * w)
+        .iter()
+        .all(|sum| *sum > tolerance)
+}
+
+fn overlap_sums(window: &DVector<f64>, hop: usize, f: impl Fn(f64) -> f64) -> DVector<f64> {
+    DVector::from_fn(hop, |i, _| {
+        (i..window.len()).step_by(hop).map(|j| f(window[j])).sum()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::Rng;
+    use rstest::rstest;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_stft_axes_and_peak() {
+        let sample_rate = 1000.0;
+        let n = 1000;
+        let x = DVector::from_fn(n, |i, _| (2.0 * PI * 125.0 * i as f64 / sample_rate).sin());
+        let spectrogram = Stft::new(Window::Hann, 64)
+            .hop(16)
+            .fft_length(128)
+            .sample_rate(sample_rate)
+            .stft(&x);
+
+        assert_eq!(spectrogram.values.shape(), (65, 64));
+        assert_relative_eq!(spectrogram.freqs[16], 125.0);
+        assert_relative_eq!(spectrogram.times[0], 0.0);
+        assert_relative_eq!(spectrogram.times[1], 0.016);
+
+        // The end frames mix in the padding
+        for k in 1..spectrogram.values.ncols() - 2 {
+            let column = spectrogram.values.column(k).map(|c| c.norm());
+            assert_eq!(column.argmax().0, 16);
+        }
+    }
+
+    #[test]
+    fn test_stft_uncentred_times() {
+        let x = DVector::zeros(100);
+        let spectrogram = Stft::new(Window::Hann, 20)
+            .padding(Padding::None)
+            .sample_rate(10.0)
+            .stft(&x);
+
+        assert_eq!(spectrogram.values.ncols(), 9);
+        assert_relative_eq!(spectrogram.times[0], 1.0);
+        assert_relative_eq!(spectrogram.times[8], 9.0);
+    }
+
+    #[rstest]
+    #[case(Window::Hann, 64, 32, 64, Padding::Reflect)]
+    #[case(Window::Hann, 64, 16, 100, Padding::Zeros)]
+    #[case(Window::Hamming, 50, 20, 64, Padding::Reflect)]
+    #[case(Window::Kaiser { beta: 8.0 }, 33, 7, 33, Padding::Zeros)]
+    fn test_istft_reconstructs(
+        #[case] window: Window,
+        #[case] window_length: usize,
+        #[case] hop: usize,
+        #[case] fft_length: usize,
+        #[case] padding: Padding,
+    ) {
+        let mut rng = rand::thread_rng();
+        let x = DVector::<f64>::from_fn(1001, |_, _| rng.gen());
+        let stft = Stft::new(window, window_length)
+            .hop(hop)
+            .fft_length(fft_length)
+            .padding(padding);
+
+        assert_relative_eq!(
+            stft.istft(&stft.stft(&x).values, x.len()),
+            x,
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_istft_without_padding() {
+        let mut rng = rand::thread_rng();
+        let x = DVector::<f64>::from_fn(500, |_, _| rng.gen());
+        let stft = Stft::new(Window::Hann, 64).padding(Padding::None);
+        let y = stft.istft(&stft.stft(&x).values, x.len());
+
+        // The first sample falls on the zero of the periodic Hann window
+        assert_relative_eq!(y.rows(1, 499), x.rows(1, 499), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_check_cola() {
+        assert!(check_cola(&Window::Hann.periodic(64), 32, 1e-10));
+        assert!(check_cola(&Window::Hann.periodic(64), 16, 1e-10));
+        assert!(check_cola(&Window::Rectangular.periodic(64), 64, 1e-10));
+        assert!(!check_cola(&Window::Hann.periodic(64), 48, 1e-10));
+        assert!(!check_cola(&Window::Hamming.symmetric(64), 32, 1e-10));
+    }
+
+    #[test]
+    fn test_check_nola() {
+        assert!(check_nola(&Window::Hann.periodic(64), 48, 1e-10));
+        assert!(check_nola(&Window::Hamming.symmetric(64), 32, 1e-10));
+        assert!(!check_nola(&Window::Hann.periodic(64), 64, 1e-10));
+        assert!(!check_nola(&Window::Rectangular.periodic(64), 80, 1e-10));
+    }
+}
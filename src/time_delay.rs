@@ -0,0 +1,337 @@
+use nalgebra::{Complex, DVector};
+
+use crate::convolution::{correlate, correlation_lags, ConvolveMode};
+use crate::fft::{irfft, rfft};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GccWeighting {
+    // Plain cross-correlation.
+    None,
+    // Phase transform: the cross-spectrum is divided by its magnitude, which whitens the
+    // source and leaves a sharp peak at the delay.
+    Phat,
+    // Smoothed coherence transform: the cross-spectrum is divided by the geometric mean of the
+    // auto-spectra. It only differs from Phat when the spectra are averaged over segments.
+    Scot,
+}
+
+#[derive(Clone, Debug)]
+pub struct Correlation {
+    // Consecutive lags in samples, positive when the first signal is delayed.
+    pub lags: DVector<isize>,
+    pub values: DVector<f64>,
+}
+
+impl Correlation {
+    // Lag and value of the maximum, interpolated to a fraction of a sample.
+    pub fn peak(&self) -> (f64, f64) {
+        let (index, value) = parabolic_peak(&self.values);
+        (self.lags[0] as f64 + index, value)
+    }
+}
+
+// Generalized cross-correlation of two recordings of the same source. By default the whole
+// recordings are correlated; with a segment length the spectra are averaged over
+// half-overlapping segments, which bounds the lags to less than a segment.
+#[derive(Clone, Debug)]
+pub struct Gcc {
+    weighting: GccWeighting,
+    segment_length: Option<usize>,
+    sample_rate: f64,
+}
+
+impl Gcc {
+    pub fn new(weighting: GccWeighting) -> Self {
+        Self {
+            weighting,
+            segment_length: None,
+            sample_rate: 1.0,
+        }
+    }
+
+    pub fn segment_length(mut self, segment_length: usize) -> Self {
+        assert!(segment_length > 0, "The segment length must be positive.");
+        self.segment_length = Some(segment_length);
+        self
+    }
+
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    pub fn correlate(&self, x: &DVector<f64>, y: &DVector<f64>) -> Correlation {
+        assert!(
+            !x.is_empty() && !y.is_empty(),
+            "The signals must not be empty."
+        );
+
+        let (segments, lags) = match self.segment_length {
+            None => (
+                vec![(x.clone(), y.clone())],
+                correlation_lags(x.len(), y.len(), ConvolveMode::Full),
+            ),
+            Some(segment_length) => {
+                let length = x.len().min(y.len());
+                assert!(
+                    segment_length <= length,
+                    "The segment length must not exceed the signal length."
+                );
+                let segments = (0..=length - segment_length)
+                    .step_by((segment_length / 2).max(1))
+                    .map(|start| {
+                        (
+                            x.rows(start, segment_length).into_owned(),
+                            y.rows(start, segment_length).into_owned(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let lags = correlation_lags(segment_length, segment_length, ConvolveMode::Full);
+                (segments, lags)
+            }
+        };
+
+        // Long enough for the linear correlation not to wrap around
+        let fft_length = lags.len().next_power_of_two();
+        let bins = fft_length / 2 + 1;
+        let mut cross = DVector::<Complex<f64>>::zeros(bins);
+        let mut x_power = DVector::<f64>::zeros(bins);
+        let mut y_power = DVector::<f64>::zeros(bins);
+        for (x_segment, y_segment) in segments.iter() {
+            let x_spectrum = rfft(&zero_padded(x_segment, fft_length));
+            let y_spectrum = rfft(&zero_padded(y_segment, fft_length));
+            cross += x_spectrum.component_mul(&y_spectrum.map(|c| c.conj()));
+            x_power += x_spectrum.map(|c| c.norm_sqr());
+            y_power += y_spectrum.map(|c| c.norm_sqr());
+        }
+        let count = segments.len() as f64;
+
+        let weighted = DVector::from_fn(bins, |k, _| {
+            let weight = match self.weighting {
+                GccWeighting::None => count,
+                GccWeighting::Phat => cross[k].norm(),
+                GccWeighting::Scot => (x_power[k] * y_power[k]).sqrt(),
+            };
+            // Bins without energy carry no information on the delay
+            if weight > 0.0 {
+                cross[k] / weight
+            } else {
+                Complex::new(0.0, 0.0)
+            }
+        });
+        let circular = irfft(&weighted, fft_length);
+
+        Correlation {
+            values: lags.map(|lag| circular[lag.rem_euclid(fft_length as isize) as usize]),
+            lags,
+        }
+    }
+
+    // Delay of x relative to y in seconds.
+    pub fn delay(&self, x: &DVector<f64>, y: &DVector<f64>) -> f64 {
+        self.correlate(x, y).peak().0 / self.sample_rate
+    }
+}
+
+// Delay of x relative to y in seconds, by GCC-PHAT over the whole recordings.
+pub fn estimate_delay(x: &DVector<f64>, y: &DVector<f64>, sample_rate: f64) -> f64 {
+    Gcc::new(GccWeighting::Phat)
+        .sample_rate(sample_rate)
+        .delay(x, y)
+}
+
+// Cross-correlation of the mean-removed inputs divided by the product of their norms, so that
+// the values lie in [-1, 1] and reach 1 where one input is a scaled copy of the other.
+pub fn correlate_normalized(
+    a: &DVector<f64>,
+    b: &DVector<f64>,
+    mode: ConvolveMode,
+) -> DVector<f64> {
+    let a = a.add_scalar(-a.mean());
+    let b = b.add_scalar(-b.mean());
+    let norm = a.norm() * b.norm();
+
+    let correlation = correlate(&a, &b, mode);
+    if norm > 0.0 {
+        correlation / norm
+    } else {
+        correlation
+    }
+}
+
+// Position and value of the maximum, refined by the vertex of the parabola through it and its
+// two neighbours. Maxima on the edges are returned as they are.
+pub fn parabolic_peak(values: &DVector<f64>) -> (f64, f64) {
+    let (i, value) = values.argmax();
+    if i == 0 || i == values.len() - 1 {
+        return (i as f64, value);
+    }
+
+    let (left, right) = (values[i - 1], values[i + 1]);
+    let curvature = left - 2.0 * value + right;
+    if curvature == 0.0 {
+        return (i as f64, value);
+    }
+    let offset = 0.5 * (left - right) / curvature;
+
+    (i as f64 + offset, value - 0.25 * (left - right) * offset)
+}
+
+fn zero_padded(x: &DVector<f64>, n: usize) -> DVector<f64> {
+    DVector::from_fn(n, |i, _| if i < x.len() { x[i] } else { 0.0 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::dvector;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use rstest::rstest;
+    use std::f64::consts::PI;
+
+    // Two windows of a circular, band-limited noise source, the first delayed by `delay`
+    // samples. `bandwidth` is the fraction of the band up to Nyquist that the source occupies.
+    fn delayed_pair(
+        length: usize,
+        delay: f64,
+        bandwidth: f64,
+        seed: u64,
+    ) -> (DVector<f64>, DVector<f64>) {
+        let n = 4 * length;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let source = DVector::from_fn(n, |_, _| rng.gen_range(-1.0..1.0));
+        let spectrum = rfft(&source);
+        let cutoff = (bandwidth * (n / 2) as f64) as usize;
+
+        let shifted = |delay: f64| {
+            let spectrum = DVector::from_fn(spectrum.len(), |k, _| {
+                if k <= cutoff {
+                    spectrum[k] * Complex::new(0.0, -2.0 * PI * k as f64 * delay / n as f64).exp()
+                } else {
+                    Complex::new(0.0, 0.0)
+                }
+            });
+            irfft(&spectrum, n).rows(length, length).into_owned()
+        };
+
+        (shifted(delay), shifted(0.0))
+    }
+
+    #[test]
+    fn test_unweighted_gcc_is_cross_correlation() {
+        let x = dvector![1.0, 2.0, 3.0, -1.0];
+        let y = dvector![0.0, 1.0, 0.5];
+        let result = Gcc::new(GccWeighting::None).correlate(&x, &y);
+        assert_eq!(result.lags, correlation_lags(4, 3, ConvolveMode::Full));
+        assert_relative_eq!(
+            result.values,
+            correlate(&x, &y, ConvolveMode::Full),
+            epsilon = 1e-12
+        );
+    }
+
+    #[rstest]
+    #[case(GccWeighting::None, None)]
+    #[case(GccWeighting::Phat, None)]
+    #[case(GccWeighting::Scot, None)]
+    #[case(GccWeighting::Phat, Some(512))]
+    #[case(GccWeighting::Scot, Some(512))]
+    fn test_integer_delay(#[case] weighting: GccWeighting, #[case] segment_length: Option<usize>) {
+        let sample_rate = 8000.0;
+        let (x, y) = delayed_pair(4096, 37.0, 1.0, 1);
+        let mut gcc = Gcc::new(weighting).sample_rate(sample_rate);
+        if let Some(segment_length) = segment_length {
+            gcc = gcc.segment_length(segment_length);
+        }
+        assert_relative_eq!(
+            gcc.delay(&x, &y),
+            37.0 / sample_rate,
+            epsilon = 0.01 / sample_rate
+        );
+        assert_relative_eq!(
+            gcc.delay(&y, &x),
+            -37.0 / sample_rate,
+            epsilon = 0.01 / sample_rate
+        );
+    }
+
+    // A parabola fits the broad peak of a narrowband source well. Phat whitens the source, so it
+    // is tested on a wideband one, and its peak is close to a sinc that the fit biases by up to
+    // about a tenth of a sample.
+    #[rstest]
+    #[case(GccWeighting::None, 0.2, 0.02)]
+    #[case(GccWeighting::Phat, 0.9, 0.15)]
+    fn test_fractional_delay(
+        #[case] weighting: GccWeighting,
+        #[case] bandwidth: f64,
+        #[case] tolerance: f64,
+    ) {
+        for delay in [-12.3, 4.5, 20.75] {
+            let (x, y) = delayed_pair(4096, delay, bandwidth, 2);
+            let estimate = Gcc::new(weighting).delay(&x, &y);
+            assert!((estimate - delay).abs() < tolerance);
+        }
+    }
+
+    #[test]
+    fn test_delay_with_noise() {
+        let sample_rate = 48_000.0;
+        let (x, y) = delayed_pair(8192, 15.0, 0.5, 3);
+        let mut rng = StdRng::seed_from_u64(4);
+        let x = x.map(|v| v + 0.2 * rng.gen_range(-1.0..1.0));
+        let y = y.map(|v| v + 0.2 * rng.gen_range(-1.0..1.0));
+
+        assert_relative_eq!(
+            estimate_delay(&x, &y, sample_rate),
+            15.0 / sample_rate,
+            epsilon = 0.5 / sample_rate
+        );
+        for weighting in [GccWeighting::Phat, GccWeighting::Scot] {
+            let gcc = Gcc::new(weighting)
+                .segment_length(1024)
+                .sample_rate(sample_rate);
+            assert_relative_eq!(
+                gcc.delay(&x, &y),
+                15.0 / sample_rate,
+                epsilon = 0.5 / sample_rate
+            );
+        }
+    }
+
+    #[test]
+    fn test_correlate_normalized() {
+        let b = dvector![0.3, -1.0, 2.0, 0.5, 0.0];
+        let a = 2.5 * &b + DVector::from_element(5, 4.0);
+        let result = correlate_normalized(&a, &b, ConvolveMode::Full);
+        assert_relative_eq!(result[4], 1.0, epsilon = 1e-12);
+        assert!(result.iter().all(|c| c.abs() <= 1.0 + 1e-12));
+
+        let result = correlate_normalized(&-a, &b, ConvolveMode::Valid);
+        assert_relative_eq!(result, dvector![-1.0], epsilon = 1e-12);
+    }
+
+    #[rstest]
+    #[case(3.3)]
+    #[case(5.0)]
+    #[case(4.5)]
+    fn test_parabolic_peak(#[case] vertex: f64) {
+        let values = DVector::from_fn(10, |i, _| 2.0 - 0.7 * (i as f64 - vertex).powi(2));
+        let (position, value) = parabolic_peak(&values);
+        assert_relative_eq!(position, vertex, epsilon = 1e-12);
+        assert_relative_eq!(value, 2.0, epsilon = 1e-12);
+
+        let correlation = Correlation {
+            lags: DVector::from_fn(10, |i, _| i as isize - 4),
+            values,
+        };
+        assert_relative_eq!(correlation.peak().0, vertex - 4.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_parabolic_peak_on_edge() {
+        assert_eq!(parabolic_peak(&dvector![3.0, 2.0, 1.0]), (0.0, 3.0));
+        assert_eq!(parabolic_peak(&dvector![1.0, 2.0, 3.0]), (2.0, 3.0));
+    }
+}
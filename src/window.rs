@@ -0,0 +1,402 @@
+use std::f64::consts::PI;
+
+use nalgebra::{Complex, DVector};
+
+use crate::math::bessel_i0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+    FlatTop,
+    Kaiser { beta: f64 },
+    // Ratio of the tapered part to the whole window, between 0 (rectangular) and 1 (Hann).
+    Tukey { alpha: f64 },
+    // Dolph-Chebyshev window with equiripple sidelobes at the given attenuation.
+    Chebyshev { attenuation_db: f64 },
+    // Standard deviation in samples.
+    Gaussian { std: f64 },
+    // First Slepian sequence with the time-halfbandwidth product nw.
+    Dpss { nw: f64 },
+}
+
+impl Window {
+    // Symmetric window of length n, as used for FIR design.
+    pub fn symmetric(&self, n: usize) -> DVector<f64> {
+        if n <= 1 {
+            return DVector::from_element(n, 1.0);
+        }
+
+        let m = (n - 1) as f64;
+        match *self {
+            Window::Chebyshev { attenuation_db } => chebyshev(n, attenuation_db),
+            Window::Dpss { nw } => dpss(n, nw),
+            _ => DVector::from_fn(n, |i, _| {
+                let x = i as f64 / m;
+                match *self {
+                    Window::Rectangular => 1.0,
+                    Window::Hann => 0.5 - 0.5 * (2.0 * PI * x).cos(),
+                    Window::Hamming => 0.54 - 0.46 * (2.0 * PI * x).cos(),
+                    Window::Blackman => {
+                        0.42 - 0.5 * (2.0 * PI * x).cos() + 0.08 * (4.0 * PI * x).cos()
+                    }
+                    Window::FlatTop => [
+                        0.21557895,
+                        0.41663158,
+                        0.277263158,
+                        0.083578947,
+                        0.006947368,
+                    ]
+                    .iter()
+                    .enumerate()
+                    .map(|(k, a)| (-1.0_f64).powi(k as i32) * a * (2.0 * PI * k as f64 * x).cos())
+                    .sum(),
+                    Window::Kaiser { beta } => {
+                        bessel_i0(beta * (1.0 - (2.0 * x - 1.0).powi(2)).sqrt()) / bessel_i0(beta)
+                    }
+                    Window::Tukey { alpha } => {
+                        let alpha = alpha.clamp(0.0, 1.0);
+                        if x < alpha / 2.0 {
+                            0.5 + 0.5 * (PI * (2.0 * x / alpha - 1.0)).cos()
+                        } else if x > 1.0 - alpha / 2.0 {
+                            0.5 + 0.5 * (PI * (2.0 * x / alpha - 2.0 / alpha + 1.0)).cos()
+                        } else {
+                            1.0
+                        }
+                    }
+                    Window::Gaussian { std } => (-0.5 * (m * (x - 0.5) / std).powi(2)).exp(),
+                    Window::Chebyshev { .. } | Window::Dpss { .. } => unreachable!(),
+                }
+            }),
+        }
+    }
+
+    // Periodic window of length n, as used for spectral analysis.
+    pub fn periodic(&self, n: usize) -> DVector<f64> {
+        self.symmetric(n + 1).rows(0, n).into_owned()
+    }
+}
+
+// Equivalent noise bandwidth in bins.
+pub fn equivalent_noise_bandwidth(window: &DVector<f64>) -> f64 {
+    window.len() as f64 * window.norm_squared() / window.sum().powi(2)
+}
+
+pub fn coherent_gain(window: &DVector<f64>) -> f64 {
+    window.sum() / window.len() as f64
+}
+
+// Loss in dB for a tone halfway between two bins, relative to a tone on a bin.
+pub fn scalloping_loss(window: &DVector<f64>) -> f64 {
+    let n = window.len() as f64;
+    let half_bin = window
+        .iter()
+        .enumerate()
+        .map(|(i, w)| w * Complex::new(0.0, -PI * i as f64 / n).exp())
+        .sum::<Complex<f64>>()
+        .norm();
+
+    -20.0 * (half_bin / window.sum()).log10()
+}
+
+fn chebyshev(n: usize, attenuation_db: f64) -> DVector<f64> {
+    let order = (n - 1) as f64;
+    let beta = ((10.0_f64.powf(attenuation_db.abs() / 20.0)).acosh() / order).cosh();
+
+    // Samples of the Chebyshev polynomial on the unit circle
+    let p = DVector::from_fn(n, |k, _| {
+        let x = beta * (PI * k as f64 / n as f64).cos();
+        let value = if x > 1.0 {
+            (order * x.acosh()).cosh()
+        } else if x < -1.0 {
+            let sign = if n % 2 == 1 { 1.0 } else { -1.0 };
+            sign * (order * (-x).acosh()).cosh()
+        } else {
+            (order * x.acos()).cos()
+        };
+        if n % 2 == 1 {
+            Complex::new(value, 0.0)
+        } else {
+            Complex::new(0.0, PI * k as f64 / n as f64).exp() * value
+        }
+    });
+    let spectrum = DVector::from_fn(n, |k, _| {
+        p.iter()
+            .enumerate()
+            .map(|(j, p)| p * Complex::new(0.0, -2.0 * PI * (j * k) as f64 / n as f64).exp())
+            .sum::<Complex<f64>>()
+            .re
+    });
+
+    let half = n / 2 + 1;
+    let w = if n % 2 == 1 {
+        DVector::from_fn(n, |i, _| spectrum[i.abs_diff(half - 1)])
+    } else {
+        DVector::from_fn(n, |i, _| {
+            if i < half - 1 {
+                spectrum[half - 1 - i]
+            } else {
+                spectrum[i + 2 - half]
+            }
+        })
+    };
+
+    let max = w.max();
+    w / max
+}
+
+// The Slepian sequence is the eigenvector for the largest eigenvalue of a symmetric tridiagonal
+// matrix, found by bisection on the eigenvalue and inverse iteration.
+fn dpss(n: usize, nw: f64) -> DVector<f64> {
+    let bandwidth = nw / n as f64;
+    let diagonal = DVector::from_fn(n, |i, _| {
+        (0.5 * (n - 1) as f64 - i as f64).powi(2) * (2.0 * PI * bandwidth).cos()
+    });
+    let off_diagonal = DVector::from_fn(n - 1, |i, _| 0.5 * ((i + 1) * (n - i - 1)) as f64);
+
+    let radius = |i: usize| {
+        let left = if i > 0 { off_diagonal[i - 1] } else { 0.0 };
+        let right = if i < n - 1 { off_diagonal[i] } else { 0.0 };
+        left + right
+    };
+    let mut low = (0..n)
+        .map(|i| diagonal[i] - radius(i))
+        .fold(f64::INFINITY, f64::min);
+    let mut high = (0..n)
+        .map(|i| diagonal[i] + radius(i))
+        .fold(f64::NEG_INFINITY, f64::max);
+    for _ in 0..200 {
+        let mid = 0.5 * (low + high);
+        if mid <= low || mid >= high {
+            break;
+        }
+        if count_eigenvalues_below(&diagonal, &off_diagonal, mid) == n {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+
+    // Shifting just above the largest eigenvalue keeps the system definite
+    let shifted = diagonal.add_scalar(-high);
+    let mut v = DVector::from_element(n, 1.0);
+    for _ in 0..3 {
+        v = solve_tridiagonal(&shifted, &off_diagonal, &v);
+        v /= v.norm();
+    }
+
+    if v.sum() < 0.0 {
+        v = -v;
+    }
+    v /= v.max();
+    if n.is_multiple_of(2) {
+        let n = n as f64;
+        v *= n * n / (n * n + nw);
+    }
+    v
+}
+
+fn count_eigenvalues_below(diagonal: &DVector<f64>, off_diagonal: &DVector<f64>, x: f64) -> usize {
+    let mut count = 0;
+    let mut q = 1.0;
+    for i in 0..diagonal.len() {
+        q = diagonal[i]
+            - x
+            - if i > 0 {
+                off_diagonal[i - 1].powi(2) / q
+            } else {
+                0.0
+            };
+        if q == 0.0 {
+            q = -f64::EPSILON;
+        }
+        if q < 0.0 {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn solve_tridiagonal(
+    diagonal: &DVector<f64>,
+    off_diagonal: &DVector<f64>,
+    rhs: &DVector<f64>,
+) -> DVector<f64> {
+    let n = diagonal.len();
+    let mut c = DVector::zeros(n);
+    let mut d = DVector::zeros(n);
+    c[0] = if n > 1 {
+        off_diagonal[0] / diagonal[0]
+    } else {
+        0.0
+    };
+    d[0] = rhs[0] / diagonal[0];
+    for i in 1..n {
+        let denom = diagonal[i] - off_diagonal[i - 1] * c[i - 1];
+        if i < n - 1 {
+            c[i] = off_diagonal[i] / denom;
+        }
+        d[i] = (rhs[i] - off_diagonal[i - 1] * d[i - 1]) / denom;
+    }
+
+    let mut x = d.clone();
+    for i in (0..n - 1).rev() {
+        x[i] = d[i] - c[i] * x[i + 1];
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::dvector;
+    use rstest::rstest;
+
+    fn is_symmetric(w: &DVector<f64>) -> bool {
+        (0..w.len()).all(|i| (w[i] - w[w.len() - 1 - i]).abs() < 1e-12)
+    }
+
+    // Fraction of the window energy inside the band |f| < bandwidth (in cycles per sample).
+    fn energy_concentration(w: &DVector<f64>, bandwidth: f64) -> f64 {
+        let mut in_band = 0.0;
+        for i in 0..w.len() {
+            for j in 0..w.len() {
+                let k = i as f64 - j as f64;
+                let kernel = if i == j {
+                    2.0 * bandwidth
+                } else {
+                    (2.0 * PI * bandwidth * k).sin() / (PI * k)
+                };
+                in_band += w[i] * w[j] * kernel;
+            }
+        }
+        in_band / w.norm_squared()
+    }
+
+    #[test]
+    fn test_window_symmetric() {
+        assert_relative_eq!(Window::Hann.symmetric(5), dvector![0.0, 0.5, 1.0, 0.5, 0.0]);
+        assert_relative_eq!(
+            Window::Hamming.symmetric(3),
+            dvector![0.08, 1.0, 0.08],
+            epsilon = 1e-15
+        );
+        assert_relative_eq!(
+            Window::Blackman.symmetric(3),
+            dvector![0.0, 1.0, 0.0],
+            epsilon = 1e-15
+        );
+        assert_relative_eq!(
+            Window::Kaiser { beta: 0.0 }.symmetric(4),
+            dvector![1.0, 1.0, 1.0, 1.0]
+        );
+        assert_relative_eq!(
+            Window::Tukey { alpha: 0.5 }.symmetric(5),
+            dvector![0.0, 1.0, 1.0, 1.0, 0.0],
+            epsilon = 1e-15
+        );
+        assert_relative_eq!(
+            Window::Gaussian { std: 1.0 }.symmetric(3),
+            dvector![(-0.5_f64).exp(), 1.0, (-0.5_f64).exp()]
+        );
+        assert_relative_eq!(
+            Window::FlatTop.symmetric(3),
+            dvector![-0.000421051, 1.000000003, -0.000421051],
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn test_window_periodic() {
+        assert_relative_eq!(
+            Window::Hann.periodic(4),
+            dvector![0.0, 0.5, 1.0, 0.5],
+            epsilon = 1e-15
+        );
+        assert_eq!(Window::Dpss { nw: 2.5 }.periodic(16).len(), 16);
+    }
+
+    #[rstest]
+    #[case(Window::Rectangular)]
+    #[case(Window::Hann)]
+    #[case(Window::FlatTop)]
+    #[case(Window::Kaiser { beta: 8.6 })]
+    #[case(Window::Tukey { alpha: 0.25 })]
+    #[case(Window::Chebyshev { attenuation_db: 80.0 })]
+    #[case(Window::Gaussian { std: 7.0 })]
+    #[case(Window::Dpss { nw: 3.0 })]
+    fn test_window_shape(#[case] window: Window) {
+        for n in [32, 33] {
+            let w = window.symmetric(n);
+            assert_eq!(w.len(), n);
+            assert!(is_symmetric(&w));
+            assert!(w.max() <= 1.0 + 1e-8);
+        }
+    }
+
+    #[rstest]
+    #[case(31, 50.0)]
+    #[case(32, 80.0)]
+    fn test_chebyshev_sidelobes(#[case] n: usize, #[case] attenuation_db: f64) {
+        let w = Window::Chebyshev { attenuation_db }.symmetric(n);
+        let response = (0..=2000)
+            .map(|k| {
+                let freq = 0.5 * k as f64 / 2000.0;
+                w.iter()
+                    .enumerate()
+                    .map(|(i, w)| w * Complex::new(0.0, -2.0 * PI * freq * i as f64).exp())
+                    .sum::<Complex<f64>>()
+                    .norm()
+            })
+            .collect::<Vec<_>>();
+
+        // The sidelobes start after the first null and all reach the same level
+        let first_null = (1..response.len())
+            .find(|k| response[*k] < response[k - 1] && response[*k] < response[k + 1])
+            .unwrap();
+        let sidelobe = response[first_null..].iter().copied().fold(0.0, f64::max);
+        assert_relative_eq!(
+            20.0 * (sidelobe / response[0]).log10(),
+            -attenuation_db,
+            epsilon = 0.1
+        );
+    }
+
+    #[test]
+    fn test_dpss_energy_concentration() {
+        let n = 64;
+        let nw = 4.0;
+        let dpss = Window::Dpss { nw }.symmetric(n);
+        let kaiser = Window::Kaiser { beta: PI * nw }.symmetric(n);
+
+        let bandwidth = nw / n as f64;
+        let concentration = energy_concentration(&dpss, bandwidth);
+        assert!(concentration > 1.0 - 1e-8);
+        assert!(concentration > energy_concentration(&kaiser, bandwidth));
+    }
+
+    #[rstest]
+    #[case(Window::Rectangular, 1.0, 1.0, 3.9224)]
+    #[case(Window::Hann, 1.5, 0.5, 1.4236)]
+    #[case(Window::Hamming, 1.3628, 0.54, 1.7514)]
+    #[case(Window::Blackman, 1.7268, 0.42, 1.0989)]
+    fn test_window_metrics(
+        #[case] window: Window,
+        #[case] enbw: f64,
+        #[case] gain: f64,
+        #[case] loss: f64,
+    ) {
+        let w = window.periodic(1024);
+        assert_relative_eq!(equivalent_noise_bandwidth(&w), enbw, epsilon = 1e-3);
+        assert_relative_eq!(coherent_gain(&w), gain, epsilon = 1e-3);
+        assert_relative_eq!(scalloping_loss(&w), loss, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_flat_top_scalloping_loss() {
+        assert!(scalloping_loss(&Window::FlatTop.periodic(1024)) < 0.02);
+    }
+}